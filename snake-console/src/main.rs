@@ -2,141 +2,455 @@ use crossterm::{
     cursor::MoveTo,
     event::{poll, read, Event, KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers},
     execute,
-    style::{Color, Print, SetBackgroundColor, SetForegroundColor},
-    terminal::{Clear, ClearType},
+    style::{Color, Print, ResetColor, SetBackgroundColor, SetForegroundColor},
+    terminal::{disable_raw_mode, enable_raw_mode, Clear, ClearType},
 };
-use snake::{game_step, Coordinate, Game, GameDisplay, GameInput, SnakeChange, Turn};
-use std::{io::stdout, thread::sleep, time::Duration};
+use snake::{
+    ai::{Autopilot, Strategy},
+    clock::Clock,
+    game_step,
+    highscores::{self, HighScoreStore, HighScores},
+    level::Level,
+    replay::{Recorder, Replay, ReplayInput},
+    segment::Segment,
+    theme::{Preset, Rgb, SnakeColors, Theme},
+    Coordinate, Difficulty, Direction, Game, GameConfig, GameDisplay, GameEvent, GameInput,
+    GameOverReason, Input, SnakeId, Speed, Walls,
+};
+use std::{
+    cell::{Cell, RefCell},
+    collections::VecDeque,
+    env, fs,
+    io::{self, stdout},
+    path::PathBuf,
+    thread::sleep,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+/// How many ticks Backspace takes back after a crash.
+const REWIND_TICKS: usize = 20;
+
+/// One player on the arrow keys and one on WASD.
+const MAX_PLAYERS: usize = 2;
+
+/// The terminal needs true colour for themes to show as intended.
+fn color(Rgb(r, g, b): Rgb) -> Color {
+    Color::Rgb { r, g, b }
+}
+
+/// Keeps the terminal in raw mode while it lives, so keys arrive as they are
+/// pressed rather than a line at a time, and puts it back however the game
+/// ends.
+struct RawMode;
+
+impl RawMode {
+    fn enable() -> RawMode {
+        enable_raw_mode().expect("Problem enabling raw mode");
+        RawMode
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = disable_raw_mode();
+    }
+}
 
-struct Console {}
+struct Console {
+    players: usize,
+    theme: Theme,
+    pending: RefCell<Vec<VecDeque<Input>>>,
+    quit: Cell<bool>,
+}
 
 impl Console {
-    fn board(f: Color, b: Color, w: usize, h: usize) {
+    fn new(players: usize, theme: Theme) -> Console {
+        Console {
+            players,
+            theme,
+            pending: RefCell::new(vec![VecDeque::new(); players]),
+            quit: Cell::new(false),
+        }
+    }
+
+    fn board(f: Color, b: Color, w: usize, h: usize, walls: Walls) {
+        // Dotted box-drawing lines for walls the snake wraps through.
+        let (horizontal, vertical) = match walls {
+            Walls::Solid => ("━", "┃"),
+            Walls::Wrap => ("┅", "┇"),
+        };
         execute!(
             stdout(),
             Clear(ClearType::FromCursorUp),
             MoveTo(0, 0),
             SetBackgroundColor(b),
             SetForegroundColor(f),
-            Print(format!("┏{}┓\n", "━".repeat(w))),
-            Print(format!("┃{}┃\n", " ".repeat(w)).repeat(h)),
-            Print(format!("┗{}┛\n", "━".repeat(w))),
+            Print(format!("┏{}┓\r\n", horizontal.repeat(w))),
+            Print(format!("{}{}{}\r\n", vertical, " ".repeat(w), vertical).repeat(h)),
+            Print(format!("┗{}┛\r\n", horizontal.repeat(w))),
             MoveTo((h + 1).try_into().unwrap(), 0)
         )
         .expect("Problem drawing board");
     }
 
-    fn score(score: usize, f: Color, b: Color, _w: usize, h: usize) {
+    fn score(score: String, f: Color, b: Color, _w: usize, h: usize) {
         execute!(
             stdout(),
             SetBackgroundColor(b),
             SetForegroundColor(f),
             MoveTo(0, (h + 2).try_into().unwrap()),
-            Print(format!("Score: {}\n", score))
+            Clear(ClearType::CurrentLine),
+            Print(format!("{}\r\n", score))
         )
         .expect("Problem writing out score");
     }
 
-    fn snake_tail(f: Color, b: Color, x: &i32, y: &i32) {
+    fn high_scores(&self, scores: &HighScores, rank: Option<usize>, h: usize) {
+        let palette = self.theme.palette(true);
+        execute!(
+            stdout(),
+            MoveTo(0, (h + 4).try_into().unwrap()),
+            SetForegroundColor(color(palette.text)),
+            Print("High scores\r\n"),
+        )
+        .expect("Problem writing out high scores");
+        for (i, entry) in scores.top(highscores::SHOWN).iter().enumerate() {
+            let text = if Some(i) == rank {
+                palette.highlight
+            } else {
+                palette.text
+            };
+            execute!(
+                stdout(),
+                SetForegroundColor(color(text)),
+                Print(format!(
+                    "{:>2}. {:<12} {:>6}  length {:<4} {} ticks\r\n",
+                    i + 1,
+                    entry.name,
+                    entry.score,
+                    entry.length,
+                    entry.ticks
+                )),
+            )
+            .expect("Problem writing out high scores");
+        }
+        execute!(stdout(), ResetColor).expect("Problem resetting colors");
+    }
+
+    fn scores(game: &Game) -> String {
+        if game.snakes.len() == 1 {
+            return format!(
+                "Score: {}  Level: {}",
+                game.snakes[0].score().total(),
+                game.level()
+            );
+        }
+        game.snakes
+            .iter()
+            .map(|s| format!("P{}: {}", s.id + 1, s.score().total()))
+            .chain([format!("Level: {}", game.level())])
+            .collect::<Vec<String>>()
+            .join("  ")
+    }
+
+    fn food(f: Color, b: Color, x: &i32, y: &i32) {
         execute!(
             stdout(),
             MoveTo((x + 1).try_into().unwrap(), (y + 1).try_into().unwrap()),
             SetBackgroundColor(b),
             SetForegroundColor(f),
-            Print("/"),
+            Print("*"),
         )
-        .expect("Problem drawing snake");
+        .expect("Problem drawing food");
     }
 
-    fn snake_head(f: Color, b: Color, x: &i32, y: &i32) {
+    fn wall(f: Color, b: Color, x: &i32, y: &i32) {
         execute!(
             stdout(),
             MoveTo((x + 1).try_into().unwrap(), (y + 1).try_into().unwrap()),
             SetBackgroundColor(b),
             SetForegroundColor(f),
-            Print(":"),
+            Print("█"),
+        )
+        .expect("Problem drawing wall");
+    }
+
+    /// Draws the segments of a snake whose position along the body passes
+    /// `which`, as lines on the board with the head an arrowhead.
+    fn snake(
+        game: &Game,
+        id: SnakeId,
+        colors: SnakeColors,
+        b: Color,
+        which: impl Fn(usize) -> bool,
+    ) {
+        let segments = game.segments(id);
+        for (i, (segment, Coordinate { x, y })) in
+            segments.iter().zip(&game.snakes[id].body).enumerate()
+        {
+            if !which(i) {
+                continue;
+            }
+            let f = match segment {
+                Segment::Head(_) => colors.head,
+                _ => colors.body,
+            };
+            execute!(
+                stdout(),
+                MoveTo((x + 1).try_into().unwrap(), (y + 1).try_into().unwrap()),
+                SetBackgroundColor(b),
+                SetForegroundColor(color(f)),
+                Print(Console::glyph(segment)),
+            )
+            .expect("Problem drawing snake");
+        }
+    }
+
+    fn glyph(segment: &Segment) -> &'static str {
+        let joins = |side| segment.sides().contains(&side);
+        match segment {
+            Segment::Head(Direction::North) => "▲",
+            Segment::Head(Direction::South) => "▼",
+            Segment::Head(Direction::East) => "▶",
+            Segment::Head(Direction::West) => "◀",
+            _ => match (
+                joins(Direction::North),
+                joins(Direction::South),
+                joins(Direction::East),
+                joins(Direction::West),
+            ) {
+                (true, true, _, _) => "┃",
+                (_, _, true, true) => "━",
+                (true, _, true, _) => "┗",
+                (true, _, _, true) => "┛",
+                (_, true, true, _) => "┏",
+                (_, true, _, true) => "┓",
+                (true, _, _, _) => "╹",
+                (_, true, _, _) => "╻",
+                (_, _, true, _) => "╺",
+                _ => "╸",
+            },
+        }
+    }
+
+    /// Key presses are shared between players, so read everything waiting
+    /// and sort it into per-snake queues.
+    fn read_keys(&self) {
+        let mut pending = self.pending.borrow_mut();
+        while poll(Duration::from_secs(0)).unwrap() {
+            if let Event::Key(KeyEvent {
+                code,
+                modifiers,
+                kind: KeyEventKind::Press,
+                state: KeyEventState::NONE,
+            }) = read().unwrap()
+            {
+                // Raw mode delivers Ctrl+C as a key press rather than a signal.
+                if modifiers == KeyModifiers::CONTROL && matches!(code, KeyCode::Char('c' | 'd')) {
+                    self.quit.set(true);
+                    continue;
+                }
+                if modifiers != KeyModifiers::NONE {
+                    continue;
+                }
+                // Player two gets WASD; alone, both sets steer snake 0.
+                let wasd = if self.players > 1 { 1 } else { 0 };
+                let (id, direction) = match code {
+                    KeyCode::Up => (0, Direction::North),
+                    KeyCode::Down => (0, Direction::South),
+                    KeyCode::Left => (0, Direction::West),
+                    KeyCode::Right => (0, Direction::East),
+                    KeyCode::Char('w' | 'W') => (wasd, Direction::North),
+                    KeyCode::Char('s' | 'S') => (wasd, Direction::South),
+                    KeyCode::Char('a' | 'A') => (wasd, Direction::West),
+                    KeyCode::Char('d' | 'D') => (wasd, Direction::East),
+                    KeyCode::Char('q' | 'Q') | KeyCode::Esc => {
+                        self.quit.set(true);
+                        continue;
+                    }
+                    _ => continue,
+                };
+                pending[id].push_back(Input::Direction(direction));
+            }
+        }
+    }
+
+    /// After a crash, waits for a key: Backspace to rewind, anything else to
+    /// finish.
+    fn offer_rewind(&self, game: &Game) -> bool {
+        let row = (usize::from(game.height) + 3).try_into().unwrap();
+        execute!(
+            stdout(),
+            MoveTo(0, row),
+            Clear(ClearType::CurrentLine),
+            Print("Backspace to rewind, any other key to finish"),
         )
-        .expect("Problem drawing snake");
+        .expect("Problem writing out rewind prompt");
+        let code = loop {
+            if let Event::Key(KeyEvent {
+                code,
+                kind: KeyEventKind::Press,
+                ..
+            }) = read().unwrap()
+            {
+                break code;
+            }
+        };
+        execute!(stdout(), MoveTo(0, row), Clear(ClearType::CurrentLine))
+            .expect("Problem clearing rewind prompt");
+        for pending in self.pending.borrow_mut().iter_mut() {
+            pending.clear();
+        }
+        code == KeyCode::Backspace
+    }
+
+    /// Whether Q, Escape, Ctrl+C or Ctrl+D has been pressed.
+    fn quit_requested(&self) -> bool {
+        self.read_keys();
+        self.quit.get()
     }
 }
 
 impl GameDisplay for Console {
     fn initialize(&self, game: &Game) {
+        let palette = self.theme.palette(false);
+        let board = color(palette.board);
         Console::board(
-            Color::White,
-            Color::Black,
+            color(palette.border),
+            board,
             game.width.into(),
             game.height.into(),
+            game.config.walls,
         );
 
-        self.update(
-            game,
-            &SnakeChange {
-                removed: None,
-                added: Some(game.snake.front().unwrap().clone()),
-            },
-        );
+        for Coordinate { x, y } in &game.obstacles {
+            Console::wall(color(palette.walls), board, x, y);
+        }
 
-        Console::score(
-            game.snake.len(),
-            Color::White,
-            Color::Black,
-            game.width.into(),
-            game.height.into(),
-        );
+        for Coordinate { x, y } in &game.food {
+            Console::food(color(palette.food), board, x, y);
+        }
+
+        // Snakes are drawn whole, as a resumed or rewound game may be well
+        // under way.
+        for snake in &game.snakes {
+            let colors = if snake.alive {
+                self.theme.player(snake.id)
+            } else {
+                self.theme.dead
+            };
+            Console::snake(game, snake.id, colors, board, |_| true);
+        }
+        self.update(game, &[]);
     }
 
     fn game_over(&self, game: &Game) {
+        let palette = self.theme.palette(true);
+        let board = color(palette.board);
         Console::board(
-            Color::Red,
-            Color::Black,
+            color(palette.border),
+            board,
             game.width.into(),
             game.height.into(),
+            game.config.walls,
         );
 
-        for Coordinate { x, y } in &game.snake {
-            Console::snake_tail(Color::DarkRed, Color::Red, x, y);
+        for Coordinate { x, y } in &game.obstacles {
+            Console::wall(color(palette.walls), board, x, y);
+        }
+
+        for Coordinate { x, y } in &game.food {
+            Console::food(color(palette.food), board, x, y);
         }
 
+        for snake in &game.snakes {
+            let colors = if Some(snake.id) == game.winner {
+                self.theme.player(snake.id)
+            } else {
+                self.theme.losers
+            };
+            Console::snake(game, snake.id, colors, board, |_| true);
+        }
+
+        let mut score = match (game.snakes.len(), game.winner) {
+            (1, _) => Console::scores(game),
+            (_, Some(id)) => format!("{}  Player {} wins!", Console::scores(game), id + 1),
+            (_, None) => format!("{}  Draw!", Console::scores(game)),
+        };
+        if let Some(reason) = &game.game_over_reason {
+            score = format!("{}  Game over: {}", score, reason);
+        }
         Console::score(
-            game.snake.len() + 1,
-            Color::Red,
-            Color::Black,
+            score,
+            color(palette.text),
+            board,
             game.width.into(),
             game.height.into(),
         );
 
-        execute!(
-            stdout(),
-            SetBackgroundColor(Color::Black),
-            SetForegroundColor(Color::White),
-        )
-        .expect("Problem resetting colors");
+        execute!(stdout(), ResetColor).expect("Problem resetting colors");
     }
 
-    fn update(&self, game: &Game, change: &SnakeChange) {
-        for Coordinate { x, y } in game.snake.iter().skip(1).take(1) {
-            Console::snake_tail(Color::Yellow, Color::Green, x, y);
+    fn update(&self, game: &Game, events: &[GameEvent]) {
+        let palette = self.theme.palette(false);
+        let board = color(palette.board);
+        // Clear tails first so a head that follows another snake's tail
+        // isn't rubbed out.
+        for event in events {
+            if let GameEvent::Moved {
+                tail: Some(Coordinate { x, y }),
+                ..
+            }
+            | GameEvent::HitWall {
+                tail: Some(Coordinate { x, y }),
+                ..
+            }
+            | GameEvent::HitSelf {
+                tail: Some(Coordinate { x, y }),
+                ..
+            }
+            | GameEvent::HitSnake {
+                tail: Some(Coordinate { x, y }),
+                ..
+            } = event
+            {
+                execute!(
+                    stdout(),
+                    MoveTo((x + 1).try_into().unwrap(), (y + 1).try_into().unwrap()),
+                    SetBackgroundColor(board),
+                    Print(" "),
+                )
+                .expect("Problem clearing snake");
+            }
         }
 
-        if let Some(Coordinate { x, y }) = change.added {
-            Console::snake_head(Color::Green, Color::DarkGreen, &x, &y);
-        }
-
-        if let Some(Coordinate { x, y }) = change.removed {
-            execute!(
-                stdout(),
-                MoveTo((x + 1).try_into().unwrap(), (y + 1).try_into().unwrap()),
-                SetBackgroundColor(Color::Black),
-                Print(" "),
-            )
-            .expect("Problem clearing snake");
+        for event in events {
+            match event {
+                GameEvent::Moved { id, .. } => {
+                    // The old head becomes body and the new last cell
+                    // becomes the tail.
+                    let last = game.snakes[*id].len().saturating_sub(1);
+                    Console::snake(game, *id, self.theme.player(*id), board, |i| {
+                        i <= 1 || i == last
+                    });
+                }
+                GameEvent::HitWall { id, .. }
+                | GameEvent::HitSelf { id, .. }
+                | GameEvent::HitSnake { id, .. } => {
+                    Console::snake(game, *id, self.theme.dead, board, |_| true);
+                }
+                GameEvent::AteFood {
+                    spawned: Some(Coordinate { x, y }),
+                    ..
+                } => Console::food(color(palette.food), board, x, y),
+                _ => {}
+            }
         }
 
         Console::score(
-            game.snake.len(),
-            Color::White,
-            Color::Black,
+            Console::scores(game),
+            color(palette.text),
+            board,
             game.width.into(),
             game.height.into(),
         );
@@ -144,35 +458,356 @@ impl GameDisplay for Console {
 }
 
 impl GameInput for Console {
-    fn poll(&self) -> Option<Turn> {
-        if poll(Duration::from_secs(0)).unwrap() {
-            return match read().unwrap() {
-                Event::Key(KeyEvent {
-                    code: KeyCode::Left,
-                    modifiers: KeyModifiers::NONE,
-                    kind: KeyEventKind::Press,
-                    state: KeyEventState::NONE,
-                }) => Some(Turn::Left),
-                Event::Key(KeyEvent {
-                    code: KeyCode::Right,
-                    modifiers: KeyModifiers::NONE,
-                    kind: KeyEventKind::Press,
-                    state: KeyEventState::NONE,
-                }) => Some(Turn::Right),
-                _ => None,
-            };
+    fn poll(&self, id: SnakeId) -> Option<Input> {
+        self.read_keys();
+        self.pending.borrow_mut().get_mut(id)?.pop_front()
+    }
+}
+
+/// High scores kept as JSON in a file.
+struct FileStore {
+    path: PathBuf,
+}
+
+impl HighScoreStore for FileStore {
+    type Error = io::Error;
+
+    fn load(&self) -> Result<HighScores, io::Error> {
+        match fs::read_to_string(&self.path) {
+            Ok(text) => Ok(serde_json::from_str(&text)?),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(HighScores::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn save(&self, scores: &HighScores) -> Result<(), io::Error> {
+        fs::write(&self.path, serde_json::to_string_pretty(scores)?)
+    }
+}
+
+struct Options {
+    config: GameConfig,
+    record: Option<PathBuf>,
+    replay: Option<Replay>,
+    save: PathBuf,
+    resume: bool,
+    autopilot: Option<Strategy>,
+    name: String,
+    scores: FileStore,
+    theme: Theme,
+}
+
+fn exit_with(message: String) -> ! {
+    // `exit` skips destructors, so `RawMode` can't put the terminal back.
+    let _ = disable_raw_mode();
+    eprintln!("{}", message);
+    std::process::exit(1);
+}
+
+fn load_level(path: &str) -> Level {
+    let text = fs::read_to_string(path)
+        .unwrap_or_else(|e| exit_with(format!("Could not read level {}: {}", path, e)));
+    Level::parse(&text).unwrap_or_else(|e| exit_with(format!("Invalid level {}: {}", path, e)))
+}
+
+fn load_replay(path: &str) -> Replay {
+    let text = fs::read_to_string(path)
+        .unwrap_or_else(|e| exit_with(format!("Could not read replay {}: {}", path, e)));
+    serde_json::from_str(&text)
+        .unwrap_or_else(|e| exit_with(format!("Invalid replay {}: {}", path, e)))
+}
+
+fn options_from_args() -> Options {
+    let mut seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Clock is before 1970")
+        .as_nanos() as u64;
+    let mut walls = Walls::Solid;
+    let mut level = None;
+    let mut players = 1;
+    let mut speed = Difficulty::Normal.speed();
+    let mut record = None;
+    let mut replay = None;
+    let mut name = env::var("USER").unwrap_or_else(|_| "player".to_string());
+    let home = env::var_os("HOME").map(PathBuf::from).unwrap_or_default();
+    let mut scores = home.join(".snake-scores.json");
+    let mut save = home.join(".snake-save.json");
+    let mut resume = false;
+    let mut autopilot = None;
+    let mut theme = Preset::Dark;
+    // Arguments that shape the game's config, which a replay brings its own of.
+    let mut config_args = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if let "--seed" | "--wrap" | "--players" | "--level" | "--tick-rate" | "--difficulty" =
+            arg.as_str()
+        {
+            config_args.push(arg.clone());
+        }
+        match arg.as_str() {
+            "--seed" => {
+                seed = args
+                    .next()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or_else(|| exit_with("--seed requires a number".to_string()))
+            }
+            "--wrap" => walls = Walls::Wrap,
+            "--players" => {
+                players = args
+                    .next()
+                    .and_then(|s| s.parse().ok())
+                    .filter(|n| (1..=MAX_PLAYERS).contains(n))
+                    .unwrap_or_else(|| {
+                        exit_with(format!(
+                            "--players requires a number from 1 to {}",
+                            MAX_PLAYERS
+                        ))
+                    })
+            }
+            "--level" => {
+                level =
+                    Some(load_level(&args.next().unwrap_or_else(|| {
+                        exit_with("--level requires a file".to_string())
+                    })))
+            }
+            "--tick-rate" => {
+                speed = args
+                    .next()
+                    .and_then(|s| s.parse().ok())
+                    .and_then(|rate| Clock::with_rate(rate).ok())
+                    .map(|clock| Speed::constant(clock.interval))
+                    .unwrap_or_else(|| {
+                        exit_with("--tick-rate requires ticks per second".to_string())
+                    })
+            }
+            "--difficulty" => {
+                speed = match args.next().as_deref() {
+                    Some("easy") => Difficulty::Easy,
+                    Some("normal") => Difficulty::Normal,
+                    Some("hard") => Difficulty::Hard,
+                    _ => exit_with("--difficulty requires easy, normal or hard".to_string()),
+                }
+                .speed()
+            }
+            "--record" => {
+                record =
+                    Some(PathBuf::from(args.next().unwrap_or_else(|| {
+                        exit_with("--record requires a file".to_string())
+                    })))
+            }
+            "--replay" => {
+                replay =
+                    Some(load_replay(&args.next().unwrap_or_else(|| {
+                        exit_with("--replay requires a file".to_string())
+                    })))
+            }
+            "--save" => {
+                save = args
+                    .next()
+                    .map(PathBuf::from)
+                    .unwrap_or_else(|| exit_with("--save requires a file".to_string()))
+            }
+            "--resume" => resume = true,
+            "--autopilot" => {
+                autopilot = Some(
+                    args.next()
+                        .unwrap_or_else(|| exit_with("--autopilot requires a strategy".to_string()))
+                        .parse()
+                        .unwrap_or_else(|e| exit_with(format!("--autopilot: {}", e))),
+                )
+            }
+            "--name" => {
+                name = args
+                    .next()
+                    .unwrap_or_else(|| exit_with("--name requires a name".to_string()))
+            }
+            "--scores" => {
+                scores = args
+                    .next()
+                    .map(PathBuf::from)
+                    .unwrap_or_else(|| exit_with("--scores requires a file".to_string()))
+            }
+            "--theme" => {
+                theme = args
+                    .next()
+                    .unwrap_or_else(|| exit_with("--theme requires a name".to_string()))
+                    .parse()
+                    .unwrap_or_else(|e| exit_with(format!("--theme: {}", e)))
+            }
+            _ => exit_with(format!("Unknown argument: {}", arg)),
         }
-        None
+    }
+    if resume && (record.is_some() || replay.is_some()) {
+        exit_with("--resume can't be combined with --record or --replay".to_string());
+    }
+    if config_args.iter().any(|a| a == "--tick-rate")
+        && config_args.iter().any(|a| a == "--difficulty")
+    {
+        exit_with("--tick-rate can't be combined with --difficulty".to_string());
+    }
+    if let (Some(_), Some(arg)) = (&replay, config_args.first()) {
+        exit_with(format!(
+            "--replay plays with the config it was recorded with, so can't be combined with {}",
+            arg
+        ));
+    }
+    let config = match (level, players) {
+        (Some(level), 1) => GameConfig::from_level(&level),
+        (Some(_), _) => exit_with("--level only supports one player".to_string()),
+        (None, 1) => GameConfig::new(30, 10),
+        (None, _) => GameConfig::versus(30, 10, players),
+    };
+    Options {
+        // A replay carries the config it was recorded with.
+        config: match &replay {
+            Some(replay) => replay.config.clone(),
+            None => config.seed(seed).walls(walls).speed(speed),
+        },
+        record,
+        replay,
+        save,
+        resume,
+        autopilot,
+        name,
+        scores: FileStore { path: scores },
+        theme: theme.theme(),
     }
 }
 
+/// Records the game in the scores file and prints the table under the board.
+fn record_high_score(game: &Game, name: &str, store: &FileStore, console: &Console) {
+    let (scores, rank) = highscores::record(store, game, name).unwrap_or_else(|e| {
+        exit_with(format!(
+            "Could not update high scores {}: {}",
+            store.path.display(),
+            e
+        ))
+    });
+    console.high_scores(&scores, rank, game.height.into());
+}
+
+/// Steps the game on the clock until it ends or `done` says to stop. An
+/// autopilot gets to look at the board before each step.
+fn run<I: GameInput>(
+    game: &mut Game,
+    console: &Console,
+    input: &I,
+    autopilot: Option<&Autopilot>,
+    done: impl Fn() -> bool,
+) {
+    let mut clock = Clock::new(game.tick_interval());
+    let start = Instant::now();
+    while !game.game_over && !done() {
+        sleep(clock.until_next(start.elapsed()));
+        for _ in 0..clock.advance(start.elapsed()) {
+            if let Some(autopilot) = autopilot {
+                autopilot.observe(game);
+            }
+            game_step(game, console, input);
+            if game.game_over || done() {
+                break;
+            }
+        }
+        clock.interval = game.tick_interval();
+    }
+}
+
+/// Picks up a game saved by quitting. The save is removed so a finished
+/// game can't be resumed again.
+fn load_game(path: &PathBuf) -> Game {
+    let text = fs::read_to_string(path).unwrap_or_else(|e| {
+        exit_with(format!(
+            "Could not read saved game {}: {}",
+            path.display(),
+            e
+        ))
+    });
+    let game = serde_json::from_str(&text)
+        .unwrap_or_else(|e| exit_with(format!("Invalid saved game {}: {}", path.display(), e)));
+    fs::remove_file(path).unwrap_or_else(|e| {
+        exit_with(format!(
+            "Could not remove saved game {}: {}",
+            path.display(),
+            e
+        ))
+    });
+    game
+}
+
+fn save_game(game: &Game, path: &PathBuf, console: &Console) {
+    let text = serde_json::to_string(game).expect("Problem encoding game");
+    fs::write(path, text)
+        .unwrap_or_else(|e| exit_with(format!("Could not save game {}: {}", path.display(), e)));
+    Console::score(
+        format!("Saved to {}, resume with --resume", path.display()),
+        color(console.theme.playing.text),
+        color(console.theme.playing.board),
+        game.width.into(),
+        game.height.into(),
+    );
+}
+
 fn main() {
-    let console = Console {};
-    let mut game = Game::new(&30, &10);
+    let options = options_from_args();
+    let mut game = if options.resume {
+        load_game(&options.save)
+    } else {
+        Game::new(&options.config)
+            .unwrap_or_else(|e| exit_with(format!("Invalid game config: {}", e)))
+    };
+    let _raw = RawMode::enable();
+    let console = Console::new(game.snakes.len(), options.theme);
     console.initialize(&game);
-    let mut counter = 0u32;
-    while !game.game_over {
-        sleep(Duration::from_millis(200));
-        game_step(&mut counter, &mut game, &console, &console);
+
+    if let Some(replay) = &options.replay {
+        let input = ReplayInput::new(replay);
+        run(&mut game, &console, &input, None, || {
+            input.finished() || console.quit_requested()
+        });
+        if console.quit_requested() {
+            game.end(GameOverReason::Quit);
+            console.game_over(&game);
+        }
+        return;
+    }
+
+    let autopilot = options.autopilot.map(|s| s.autopilot());
+    let source: &dyn GameInput = match &autopilot {
+        Some(autopilot) => autopilot,
+        None => &console,
+    };
+    let recorder = Recorder::new(source, &game.config);
+    loop {
+        run(&mut game, &console, &recorder, autopilot.as_ref(), || {
+            console.quit_requested()
+        });
+        let rewind = game.game_over
+            && autopilot.is_none()
+            && game.rewindable() > 0
+            && console.offer_rewind(&game);
+        if !rewind {
+            break;
+        }
+        game.rewind(REWIND_TICKS);
+        recorder.rewind(&game);
+        console.initialize(&game);
+    }
+    if let Some(path) = &options.record {
+        let text = serde_json::to_string(&recorder.replay()).expect("Problem encoding replay");
+        fs::write(path, text).unwrap_or_else(|e| {
+            exit_with(format!("Could not save replay {}: {}", path.display(), e))
+        });
+    }
+    if !game.game_over {
+        // Like its scores, an autopilot game isn't the player's to resume,
+        // and saving it would overwrite theirs.
+        if autopilot.is_none() {
+            save_game(&game, &options.save, &console);
+        }
+        return;
+    }
+    // Autopilot scores aren't the player's to keep.
+    if game.snakes.len() == 1 && autopilot.is_none() {
+        record_high_score(&game, &options.name, &options.scores, &console);
     }
 }
@@ -0,0 +1,447 @@
+//! Computer-controlled snakes.
+//!
+//! Each strategy is a [`sim::Agent`](crate::sim::Agent), so it can be run
+//! headless in the simulator. To drive a front end, wrap one in an
+//! [`Autopilot`], which implements `GameInput`, and call
+//! [`Autopilot::observe`] before each `game_step`.
+
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::sim::{Agent, Observation};
+use crate::{Coordinate, Direction, Game, GameInput, Input, SnakeId, Walls};
+
+/// Heads for the nearest food, only looking one move ahead.
+pub struct Greedy;
+
+/// Follows the shortest path to food, but only if the snake will still
+/// have room to move once it gets there; otherwise it heads for the
+/// largest open space.
+pub struct Pathfinder;
+
+/// Follows a fixed route through every cell of the board, so a lone snake
+/// never crashes until it fills the board. Boards with walls inside or an
+/// odd width and height have no such route and fall back to the pathfinder.
+#[derive(Default)]
+pub struct Hamiltonian {
+    /// The board the route was built for: its size and whether it has walls
+    /// inside. A new game on a different board builds a new one.
+    board: Option<(u16, u16, bool)>,
+    /// Each cell's successor on the route, if the board has one.
+    cycle: Option<HashMap<Coordinate, Coordinate>>,
+}
+
+/// The built-in strategies, by name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Strategy {
+    Greedy,
+    Pathfinder,
+    Hamiltonian,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct UnknownStrategy(pub String);
+
+/// Steers every live snake with an agent.
+pub struct Autopilot {
+    agent: RefCell<Box<dyn Agent>>,
+    moves: RefCell<Vec<Option<Input>>>,
+}
+
+impl Autopilot {
+    pub fn new(agent: impl Agent + 'static) -> Autopilot {
+        Autopilot {
+            agent: RefCell::new(Box::new(agent)),
+            moves: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Decides this tick's moves. Call before each `game_step`.
+    pub fn observe(&self, game: &Game) {
+        let mut agent = self.agent.borrow_mut();
+        *self.moves.borrow_mut() = game
+            .snakes
+            .iter()
+            .map(|s| match s.alive {
+                true => agent.act(&Observation { id: s.id, game }),
+                false => None,
+            })
+            .collect();
+    }
+}
+
+impl GameInput for Autopilot {
+    fn poll(&self, id: SnakeId) -> Option<Input> {
+        self.moves.borrow_mut().get_mut(id)?.take()
+    }
+}
+
+impl Strategy {
+    pub const ALL: [Strategy; 3] = [
+        Strategy::Greedy,
+        Strategy::Pathfinder,
+        Strategy::Hamiltonian,
+    ];
+
+    pub fn autopilot(&self) -> Autopilot {
+        match self {
+            Strategy::Greedy => Autopilot::new(Greedy),
+            Strategy::Pathfinder => Autopilot::new(Pathfinder),
+            Strategy::Hamiltonian => Autopilot::new(Hamiltonian::default()),
+        }
+    }
+}
+
+impl FromStr for Strategy {
+    type Err = UnknownStrategy;
+
+    fn from_str(s: &str) -> Result<Strategy, UnknownStrategy> {
+        Strategy::ALL
+            .into_iter()
+            .find(|strategy| strategy.to_string() == s)
+            .ok_or_else(|| UnknownStrategy(s.to_string()))
+    }
+}
+
+impl fmt::Display for Strategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Strategy::Greedy => write!(f, "greedy"),
+            Strategy::Pathfinder => write!(f, "pathfinder"),
+            Strategy::Hamiltonian => write!(f, "hamiltonian"),
+        }
+    }
+}
+
+impl fmt::Display for UnknownStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unknown strategy {:?}, expected one of greedy, pathfinder or hamiltonian",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for UnknownStrategy {}
+
+impl Agent for Greedy {
+    fn act(&mut self, o: &Observation) -> Option<Input> {
+        let (direction, _) = open_moves(o)
+            .into_iter()
+            .min_by_key(|(_, c)| nearest_food(o, c))?;
+        steer(o, direction)
+    }
+}
+
+impl Agent for Pathfinder {
+    fn act(&mut self, o: &Observation) -> Option<Input> {
+        let moves = open_moves(o);
+        let length = o.snake().len();
+        if let Some(first) = path_to_food(o) {
+            if let Some((_, c)) = moves.iter().find(|(d, _)| *d == first) {
+                if room(o, c, length) >= length {
+                    return steer(o, first);
+                }
+            }
+        }
+        let heading = o.snake().direction();
+        let (direction, _) = moves
+            .into_iter()
+            .max_by_key(|(d, c)| (room(o, c, usize::MAX), *d == heading))?;
+        steer(o, direction)
+    }
+}
+
+impl Agent for Hamiltonian {
+    fn act(&mut self, o: &Observation) -> Option<Input> {
+        let board = (o.game.width, o.game.height, o.game.obstacles.is_empty());
+        if self.board != Some(board) {
+            self.board = Some(board);
+            self.cycle = cycle(o.game);
+        }
+        let cycle = match &self.cycle {
+            Some(cycle) => cycle,
+            None => return Pathfinder.act(o),
+        };
+        let head = o.head();
+        let next = cycle.get(head)?;
+        let heading = o.snake().direction();
+        // The route can be run either way round; go the way that doesn't
+        // need the snake to reverse.
+        let target = if o.neighbour(head, heading.opposite()) == *next {
+            cycle
+                .iter()
+                .find(|(_, to)| *to == head)
+                .map(|(from, _)| from)?
+        } else {
+            next
+        };
+        let direction = Direction::ALL
+            .into_iter()
+            .find(|d| o.neighbour(head, *d) == *target)?;
+        steer(o, direction)
+    }
+}
+
+fn steer(o: &Observation, direction: Direction) -> Option<Input> {
+    if direction == o.snake().direction() {
+        None
+    } else {
+        Some(Input::Direction(direction))
+    }
+}
+
+/// Moves the snake can make this tick without crashing.
+fn open_moves(o: &Observation) -> Vec<(Direction, Coordinate)> {
+    let heading = o.snake().direction();
+    Direction::ALL
+        .into_iter()
+        .filter(|d| *d != heading.opposite())
+        .map(|d| (d, o.neighbour(o.head(), d)))
+        .filter(|(_, c)| o.open(c))
+        .collect()
+}
+
+fn distance(game: &Game, a: &Coordinate, b: &Coordinate) -> i32 {
+    let (dx, dy) = ((a.x - b.x).abs(), (a.y - b.y).abs());
+    match game.config.walls {
+        Walls::Solid => dx + dy,
+        Walls::Wrap => dx.min(i32::from(game.width) - dx) + dy.min(i32::from(game.height) - dy),
+    }
+}
+
+fn nearest_food(o: &Observation, from: &Coordinate) -> i32 {
+    o.game
+        .food
+        .iter()
+        .map(|f| distance(o.game, from, f))
+        .min()
+        .unwrap_or(0)
+}
+
+/// The first move of the shortest path to any food.
+fn path_to_food(o: &Observation) -> Option<Direction> {
+    let head = o.head();
+    let mut seen = HashSet::from([head.clone()]);
+    let mut queue: VecDeque<(Coordinate, Direction)> =
+        open_moves(o).into_iter().map(|(d, c)| (c, d)).collect();
+    seen.extend(queue.iter().map(|(c, _)| c.clone()));
+    while let Some((c, first)) = queue.pop_front() {
+        if o.game.food.contains(&c) {
+            return Some(first);
+        }
+        for d in Direction::ALL {
+            let next = o.neighbour(&c, d);
+            if !o.blocked(&next) && seen.insert(next.clone()) {
+                queue.push_back((next, first));
+            }
+        }
+    }
+    None
+}
+
+/// How many cells can be reached from `start`, counting at most `limit`.
+fn room(o: &Observation, start: &Coordinate, limit: usize) -> usize {
+    let mut seen = HashSet::from([start.clone()]);
+    let mut queue = VecDeque::from([start.clone()]);
+    while let Some(c) = queue.pop_front() {
+        if seen.len() >= limit {
+            break;
+        }
+        for d in Direction::ALL {
+            let next = o.neighbour(&c, d);
+            if !o.blocked(&next) && seen.insert(next.clone()) {
+                queue.push_back(next);
+            }
+        }
+    }
+    seen.len()
+}
+
+/// A route visiting every cell once and returning to the start, if the
+/// board has one that's easy to build: no walls inside and an even side.
+fn cycle(game: &Game) -> Option<HashMap<Coordinate, Coordinate>> {
+    let (w, h) = (i32::from(game.width), i32::from(game.height));
+    if !game.obstacles.is_empty() || w < 2 || h < 2 {
+        return None;
+    }
+    // Build it for an even height, transposing if only the width is even.
+    let (w, h, transpose) = match (w % 2, h % 2) {
+        (_, 0) => (w, h, false),
+        (0, _) => (h, w, true),
+        _ => return None,
+    };
+    // Along the top row, snake back and forth over the rest of the board
+    // leaving the first column free, then back up the first column.
+    let mut route: Vec<Coordinate> = (0..w).map(|x| Coordinate { x, y: 0 }).collect();
+    for y in 1..h {
+        let xs: Vec<i32> = if y % 2 == 1 {
+            (1..w).rev().collect()
+        } else {
+            (1..w).collect()
+        };
+        route.extend(xs.into_iter().map(|x| Coordinate { x, y }));
+    }
+    route.extend((1..h).rev().map(|y| Coordinate { x: 0, y }));
+    if transpose {
+        for c in route.iter_mut() {
+            *c = Coordinate { x: c.y, y: c.x };
+        }
+    }
+    Some(
+        route
+            .iter()
+            .zip(route.iter().cycle().skip(1))
+            .map(|(a, b)| (a.clone(), b.clone()))
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use crate::ai::{cycle, Greedy, Hamiltonian, Pathfinder, Strategy, UnknownStrategy};
+    use crate::sim::{Agent, Observation, Simulator};
+    use crate::{game_step, Coordinate, Direction, Game, GameConfig, Input, NoDisplay, Walls};
+    use test_case::test_case;
+
+    fn act(agent: &mut impl Agent, game: &Game) -> Option<Input> {
+        agent.act(&Observation { id: 0, game })
+    }
+
+    #[test]
+    fn greedy_turns_towards_food() {
+        let mut game = Game::new(&GameConfig::new(20, 20)).unwrap();
+        game.food = HashSet::from([Coordinate { x: 10, y: 3 }]);
+        assert_eq!(
+            act(&mut Greedy, &game),
+            Some(Input::Direction(Direction::North))
+        );
+    }
+
+    #[test]
+    fn greedy_avoids_walls() {
+        let mut game =
+            Game::new(&GameConfig::new(20, 20).start(Coordinate { x: 19, y: 0 })).unwrap();
+        game.food = HashSet::from([Coordinate { x: 25, y: 0 }]);
+        assert_eq!(
+            act(&mut Greedy, &game),
+            Some(Input::Direction(Direction::South))
+        );
+    }
+
+    #[test]
+    fn pathfinder_goes_around_walls() {
+        let walls = (5..=15).map(|y| Coordinate { x: 12, y }).collect();
+        let mut game = Game::new(&GameConfig::new(20, 20).obstacles(walls)).unwrap();
+        game.food = HashSet::from([Coordinate { x: 14, y: 10 }]);
+        let autopilot = Strategy::Pathfinder.autopilot();
+        while game.score(0).food == 0 && game.ticks < 20 {
+            autopilot.observe(&game);
+            game_step(&mut game, &NoDisplay, &autopilot);
+        }
+        assert!(!game.game_over);
+        assert_eq!(game.ticks, 16);
+    }
+
+    #[test]
+    fn pathfinder_stays_out_of_dead_ends() {
+        // Food at the end of a corridor too short for the snake to turn
+        // round in.
+        let walls = HashSet::from([
+            Coordinate { x: 11, y: 9 },
+            Coordinate { x: 12, y: 9 },
+            Coordinate { x: 13, y: 10 },
+            Coordinate { x: 11, y: 11 },
+            Coordinate { x: 12, y: 11 },
+        ]);
+        let mut game = Game::new(&GameConfig::new(20, 20).obstacles(walls).length(1)).unwrap();
+        game.snakes[0].body.extend([
+            Coordinate { x: 9, y: 10 },
+            Coordinate { x: 8, y: 10 },
+            Coordinate { x: 7, y: 10 },
+        ]);
+        game.food = HashSet::from([Coordinate { x: 12, y: 10 }]);
+        assert_eq!(act(&mut Greedy, &game), None);
+        assert_ne!(act(&mut Pathfinder, &game), None);
+    }
+
+    #[test_case(6, 4)]
+    #[test_case(5, 4)]
+    #[test_case(4, 5)]
+    fn cycle_visits_every_cell_once(width: u16, height: u16) {
+        let game = Game::new(&GameConfig::new(width, height)).unwrap();
+        let cycle = cycle(&game).unwrap();
+        assert_eq!(cycle.len(), usize::from(width * height));
+        let mut c = Coordinate { x: 0, y: 0 };
+        let mut seen = HashSet::new();
+        while seen.insert(c.clone()) {
+            let next = cycle[&c].clone();
+            assert_eq!((next.x - c.x).abs() + (next.y - c.y).abs(), 1);
+            c = next;
+        }
+        assert_eq!(seen.len(), cycle.len());
+    }
+
+    #[test]
+    fn odd_boards_have_no_cycle() {
+        assert!(cycle(&Game::new(&GameConfig::new(5, 5)).unwrap()).is_none());
+    }
+
+    #[test_case(Walls::Solid)]
+    #[test_case(Walls::Wrap)]
+    fn hamiltonian_fills_the_board(walls: Walls) {
+        let stats = Simulator::new(GameConfig::new(8, 6).walls(walls))
+            .max_ticks(5000)
+            .run(&mut Hamiltonian::default());
+        // It only stops once there's nowhere left to grow into.
+        assert_eq!(stats.lengths, vec![48]);
+    }
+
+    #[test]
+    fn hamiltonian_rebuilds_its_route_for_a_new_board() {
+        let mut agent = Hamiltonian::default();
+        for (width, height) in [(8, 6), (4, 4), (6, 6)] {
+            let stats = Simulator::new(GameConfig::new(width, height))
+                .max_ticks(5000)
+                .run(&mut agent);
+            assert_eq!(stats.lengths, vec![usize::from(width * height)]);
+        }
+    }
+
+    #[test]
+    fn pathfinder_beats_greedy() {
+        let sim = Simulator::new(GameConfig::new(12, 12)).max_ticks(3000);
+        let greedy = sim.run_many(30, Greedy);
+        let pathfinder = sim.run_many(30, Pathfinder);
+        assert!(greedy.mean_length() > 4.0);
+        assert!(pathfinder.mean_length() > greedy.mean_length());
+    }
+
+    #[test]
+    fn autopilot_drives_game_step() {
+        let autopilot = Strategy::Greedy.autopilot();
+        let mut game = Game::new(&GameConfig::new(20, 20)).unwrap();
+        game.food = HashSet::from([Coordinate { x: 10, y: 3 }]);
+        for _ in 0..7 {
+            autopilot.observe(&game);
+            game_step(&mut game, &NoDisplay, &autopilot);
+        }
+        assert_eq!(game.score(0).food, 10);
+    }
+
+    #[test]
+    fn strategies_parse_by_name() {
+        for strategy in Strategy::ALL {
+            assert_eq!(strategy.to_string().parse(), Ok(strategy));
+        }
+        assert_eq!(
+            "random".parse::<Strategy>(),
+            Err(UnknownStrategy("random".to_string()))
+        );
+    }
+}
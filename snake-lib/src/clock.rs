@@ -0,0 +1,208 @@
+//! Fixed-timestep scheduling, independent of how often the caller checks.
+//!
+//! Front ends pass in the current time from whatever source they have
+//! (`Instant` in a terminal, `performance.now()` in a browser) and step the
+//! game however many times [`Clock::advance`] says are due.
+
+use std::fmt;
+use std::time::Duration;
+
+pub const DEFAULT_INTERVAL: Duration = Duration::from_millis(200);
+pub const DEFAULT_CATCH_UP: u32 = 5;
+
+#[derive(Clone, Debug)]
+pub struct Clock {
+    pub interval: Duration,
+    /// Most ticks run by one call to `advance`. If the caller falls further
+    /// behind than this (a backgrounded tab, say), the missed ticks are
+    /// dropped rather than played back in a burst.
+    pub max_catch_up: u32,
+    next: Option<Duration>,
+}
+
+/// Why a tick rate can't drive a clock.
+#[derive(Debug, PartialEq, Eq)]
+pub enum RateError {
+    /// Not a positive number, or so slow the interval can't be represented.
+    OutOfRange,
+    /// So fast the interval rounds down to nothing.
+    TooFast,
+}
+
+impl Default for Clock {
+    fn default() -> Clock {
+        Clock::new(DEFAULT_INTERVAL)
+    }
+}
+
+impl Clock {
+    pub fn new(interval: Duration) -> Clock {
+        Clock {
+            interval,
+            max_catch_up: DEFAULT_CATCH_UP,
+            next: None,
+        }
+    }
+
+    /// A clock running `rate` ticks per second.
+    pub fn with_rate(rate: f64) -> Result<Clock, RateError> {
+        let interval =
+            Duration::try_from_secs_f64(1.0 / rate).map_err(|_| RateError::OutOfRange)?;
+        if interval.is_zero() {
+            return Err(RateError::TooFast);
+        }
+        Ok(Clock::new(interval))
+    }
+
+    pub fn max_catch_up(mut self, max_catch_up: u32) -> Clock {
+        self.max_catch_up = max_catch_up;
+        self
+    }
+
+    /// How many ticks are due at `now`. The first call only starts the
+    /// clock, so the first tick comes one interval later.
+    pub fn advance(&mut self, now: Duration) -> u32 {
+        let mut next = match self.next {
+            Some(next) => next,
+            None => {
+                self.next = Some(now + self.interval);
+                return 0;
+            }
+        };
+        let mut ticks = 0;
+        while now >= next && ticks < self.max_catch_up {
+            next += self.interval;
+            ticks += 1;
+        }
+        if now >= next {
+            next = now + self.interval;
+        }
+        self.next = Some(next);
+        ticks
+    }
+
+    /// Time left until the next tick is due.
+    pub fn until_next(&self, now: Duration) -> Duration {
+        self.next
+            .map_or(Duration::ZERO, |next| next.saturating_sub(now))
+    }
+
+    /// How far through the current interval `now` is, from 0 just after a
+    /// tick to 1 as the next falls due, for drawing movement between ticks.
+    /// A stopped clock is at 1, so the board is shown as it stands.
+    pub fn progress(&self, now: Duration) -> f64 {
+        if self.interval.is_zero() {
+            return 1.0;
+        }
+        let left = self.until_next(now).as_secs_f64() / self.interval.as_secs_f64();
+        (1.0 - left).clamp(0.0, 1.0)
+    }
+
+    /// Stops the clock; the next `advance` starts it again. Use after a
+    /// pause so the paused time isn't caught up.
+    pub fn reset(&mut self) {
+        self.next = None;
+    }
+}
+
+impl fmt::Display for RateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RateError::OutOfRange => write!(f, "tick rate must be a positive number"),
+            RateError::TooFast => write!(f, "tick rate is too fast to time"),
+        }
+    }
+}
+
+impl std::error::Error for RateError {}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use crate::clock::{Clock, RateError};
+    use test_case::test_case;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn first_advance_starts_the_clock() {
+        let mut clock = Clock::new(ms(100));
+        assert_eq!(clock.until_next(ms(1000)), Duration::ZERO);
+        assert_eq!(clock.advance(ms(1000)), 0);
+        assert_eq!(clock.until_next(ms(1030)), ms(70));
+    }
+
+    #[test_case(1099, 0 ; "before interval")]
+    #[test_case(1100, 1 ; "on interval")]
+    #[test_case(1250, 2 ; "two intervals")]
+    #[test_case(1500, 5 ; "at catch up limit")]
+    #[test_case(5000, 5 ; "beyond catch up limit")]
+    fn ticks_due(now: u64, expected: u32) {
+        let mut clock = Clock::new(ms(100));
+        clock.advance(ms(1000));
+        assert_eq!(clock.advance(ms(now)), expected);
+    }
+
+    #[test]
+    fn ticks_do_not_depend_on_how_often_advance_is_called() {
+        let mut every_frame = Clock::new(ms(100));
+        let mut occasionally = Clock::new(ms(100));
+        every_frame.advance(ms(0));
+        occasionally.advance(ms(0));
+        let fast: u32 = (1..=60).map(|f| every_frame.advance(ms(f * 16))).sum();
+        let slow: u32 = (1..=20).map(|f| occasionally.advance(ms(f * 48))).sum();
+        assert_eq!(fast, 9);
+        assert_eq!(slow, 9);
+    }
+
+    #[test]
+    fn backlog_beyond_limit_is_dropped() {
+        let mut clock = Clock::new(ms(100)).max_catch_up(2);
+        clock.advance(ms(0));
+        assert_eq!(clock.advance(ms(1000)), 2);
+        assert_eq!(clock.advance(ms(1050)), 0);
+        assert_eq!(clock.advance(ms(1100)), 1);
+    }
+
+    #[test]
+    fn reset_skips_paused_time() {
+        let mut clock = Clock::new(ms(100));
+        clock.advance(ms(0));
+        clock.reset();
+        assert_eq!(clock.advance(ms(10_000)), 0);
+        assert_eq!(clock.advance(ms(10_100)), 1);
+    }
+
+    #[test_case(1000, 0.0 ; "just ticked")]
+    #[test_case(1025, 0.25 ; "part way")]
+    #[test_case(1099, 0.99 ; "almost due")]
+    #[test_case(1300, 1.0 ; "overdue")]
+    fn progress_through_interval(now: u64, expected: f64) {
+        let mut clock = Clock::new(ms(100));
+        clock.advance(ms(900));
+        clock.advance(ms(1000));
+        assert!((clock.progress(ms(now)) - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn stopped_clock_has_no_progress_to_make() {
+        assert_eq!(Clock::new(ms(100)).progress(ms(50)), 1.0);
+    }
+
+    #[test]
+    fn rate_sets_interval() {
+        assert_eq!(Clock::with_rate(4.0).unwrap().interval, ms(250));
+    }
+
+    #[test_case(0.0, RateError::OutOfRange ; "zero")]
+    #[test_case(-4.0, RateError::OutOfRange ; "negative")]
+    #[test_case(f64::NAN, RateError::OutOfRange ; "not a number")]
+    #[test_case(1e-300, RateError::OutOfRange ; "too slow")]
+    #[test_case(1e300, RateError::TooFast ; "too fast")]
+    fn unusable_rate_is_rejected(rate: f64, expected: RateError) {
+        assert_eq!(Clock::with_rate(rate).unwrap_err(), expected);
+    }
+}
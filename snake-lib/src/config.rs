@@ -0,0 +1,451 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::level::Level;
+use crate::{Coordinate, Difficulty, Direction, ScoreRules, Speed};
+
+const DEFAULT_SEED: u64 = 0x5EED;
+const DEFAULT_LENGTH: u32 = 4;
+const DEFAULT_FOOD: usize = 1;
+const DEFAULT_REWIND: usize = 100;
+
+/// The most cells a board may have. Configs can come from files, and every
+/// food spawn looks at the whole board.
+pub const MAX_CELLS: usize = 1 << 16;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Growth {
+    /// Grow by this many segments for each piece of food eaten.
+    PerFood(u32),
+    /// Grow by `by` segments every `every` ticks whether or not food is eaten.
+    Timed { every: u32, by: u32 },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Walls {
+    /// Leaving the board ends the game.
+    Solid,
+    /// Leaving the board re-enters from the opposite edge.
+    Wrap,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SnakeStart {
+    pub position: Coordinate,
+    pub direction: Direction,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GameConfig {
+    pub width: u16,
+    pub height: u16,
+    /// One entry per snake. Defaults to a single snake in the centre of the
+    /// board heading east.
+    pub snakes: Vec<SnakeStart>,
+    pub length: u32,
+    pub growth: Growth,
+    pub walls: Walls,
+    pub obstacles: HashSet<Coordinate>,
+    pub food: usize,
+    pub scoring: ScoreRules,
+    pub speed: Speed,
+    /// How many recent ticks `Game::rewind` can undo; zero turns it off.
+    #[cfg_attr(feature = "serde", serde(default = "default_rewind"))]
+    pub rewind: usize,
+    pub seed: u64,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ConfigError {
+    EmptyBoard { width: u16, height: u16 },
+    BoardTooLarge { width: u16, height: u16 },
+    NoSnakes,
+    StartOutOfBounds(Coordinate),
+    StartOnObstacle(Coordinate),
+    StartsOverlap(Coordinate),
+    ObstacleOutOfBounds(Coordinate),
+    ZeroLength,
+    ZeroGrowthInterval,
+    ZeroTickInterval,
+}
+
+impl GameConfig {
+    pub fn new(width: u16, height: u16) -> GameConfig {
+        GameConfig {
+            width,
+            height,
+            snakes: vec![SnakeStart {
+                position: Coordinate {
+                    x: (width / 2).into(),
+                    y: (height / 2).into(),
+                },
+                direction: Direction::East,
+            }],
+            length: DEFAULT_LENGTH,
+            growth: Growth::PerFood(3),
+            walls: Walls::Solid,
+            obstacles: HashSet::new(),
+            food: DEFAULT_FOOD,
+            scoring: ScoreRules::default(),
+            speed: Speed::default(),
+            rewind: DEFAULT_REWIND,
+            seed: DEFAULT_SEED,
+        }
+    }
+
+    /// Board size, walls and (if marked) start position taken from a level map.
+    pub fn from_level(level: &Level) -> GameConfig {
+        let config = GameConfig::new(level.width, level.height).obstacles(level.walls.clone());
+        match &level.start {
+            Some(start) => config.start(start.clone()),
+            None => config,
+        }
+    }
+
+    /// Where the first snake starts. Does nothing if there are no snakes,
+    /// which `validate` reports.
+    pub fn start(mut self, start: Coordinate) -> GameConfig {
+        if let Some(first) = self.snakes.first_mut() {
+            first.position = start;
+        }
+        self
+    }
+
+    /// Which way the first snake starts heading. Like `start`, does nothing
+    /// if there are no snakes.
+    pub fn direction(mut self, direction: Direction) -> GameConfig {
+        if let Some(first) = self.snakes.first_mut() {
+            first.direction = direction;
+        }
+        self
+    }
+
+    pub fn add_snake(mut self, position: Coordinate, direction: Direction) -> GameConfig {
+        self.snakes.push(SnakeStart {
+            position,
+            direction,
+        });
+        self
+    }
+
+    /// A game for `players` snakes spread across the board, alternately
+    /// heading east and west.
+    pub fn versus(width: u16, height: u16, players: usize) -> GameConfig {
+        let mut config = GameConfig::new(width, height);
+        config.snakes = (0..players)
+            .map(|i| {
+                let east = i % 2 == 0;
+                // An empty board is left for `validate` to report.
+                let x = if east {
+                    width / 4
+                } else {
+                    (width - width / 4).saturating_sub(1)
+                };
+                let y = (i + 1) * usize::from(height) / (players + 1);
+                SnakeStart {
+                    position: Coordinate {
+                        x: x.into(),
+                        y: y as i32,
+                    },
+                    direction: if east {
+                        Direction::East
+                    } else {
+                        Direction::West
+                    },
+                }
+            })
+            .collect();
+        config
+    }
+
+    pub fn length(mut self, length: u32) -> GameConfig {
+        self.length = length;
+        self
+    }
+
+    pub fn growth(mut self, growth: Growth) -> GameConfig {
+        self.growth = growth;
+        self
+    }
+
+    pub fn walls(mut self, walls: Walls) -> GameConfig {
+        self.walls = walls;
+        self
+    }
+
+    pub fn obstacles(mut self, obstacles: HashSet<Coordinate>) -> GameConfig {
+        self.obstacles = obstacles;
+        self
+    }
+
+    pub fn food(mut self, food: usize) -> GameConfig {
+        self.food = food;
+        self
+    }
+
+    pub fn scoring(mut self, scoring: ScoreRules) -> GameConfig {
+        self.scoring = scoring;
+        self
+    }
+
+    pub fn speed(mut self, speed: Speed) -> GameConfig {
+        self.speed = speed;
+        self
+    }
+
+    pub fn difficulty(self, difficulty: Difficulty) -> GameConfig {
+        self.speed(difficulty.speed())
+    }
+
+    pub fn rewind(mut self, rewind: usize) -> GameConfig {
+        self.rewind = rewind;
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> GameConfig {
+        self.seed = seed;
+        self
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.width == 0 || self.height == 0 {
+            return Err(ConfigError::EmptyBoard {
+                width: self.width,
+                height: self.height,
+            });
+        }
+        if usize::from(self.width) * usize::from(self.height) > MAX_CELLS {
+            return Err(ConfigError::BoardTooLarge {
+                width: self.width,
+                height: self.height,
+            });
+        }
+        if self.snakes.is_empty() {
+            return Err(ConfigError::NoSnakes);
+        }
+        let mut starts = HashSet::new();
+        for SnakeStart { position, .. } in &self.snakes {
+            if !self.on_board(position) {
+                return Err(ConfigError::StartOutOfBounds(position.clone()));
+            }
+            if self.obstacles.contains(position) {
+                return Err(ConfigError::StartOnObstacle(position.clone()));
+            }
+            if !starts.insert(position) {
+                return Err(ConfigError::StartsOverlap(position.clone()));
+            }
+        }
+        if let Some(c) = self.obstacles.iter().find(|c| !self.on_board(c)) {
+            return Err(ConfigError::ObstacleOutOfBounds(c.clone()));
+        }
+        if self.length == 0 {
+            return Err(ConfigError::ZeroLength);
+        }
+        if let Growth::Timed { every: 0, .. } = self.growth {
+            return Err(ConfigError::ZeroGrowthInterval);
+        }
+        if self.speed.start.is_zero() || self.speed.fastest.is_zero() {
+            return Err(ConfigError::ZeroTickInterval);
+        }
+        Ok(())
+    }
+
+    fn on_board(&self, c: &Coordinate) -> bool {
+        c.x >= 0 && c.x < self.width.into() && c.y >= 0 && c.y < self.height.into()
+    }
+}
+
+#[cfg(feature = "serde")]
+fn default_rewind() -> usize {
+    DEFAULT_REWIND
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::EmptyBoard { width, height } => {
+                write!(f, "board must not be empty (got {}x{})", width, height)
+            }
+            ConfigError::BoardTooLarge { width, height } => write!(
+                f,
+                "board must have at most {} cells (got {}x{})",
+                MAX_CELLS, width, height
+            ),
+            ConfigError::NoSnakes => write!(f, "game needs at least one snake"),
+            ConfigError::StartOutOfBounds(Coordinate { x, y }) => {
+                write!(f, "start position ({}, {}) is off the board", x, y)
+            }
+            ConfigError::StartOnObstacle(Coordinate { x, y }) => {
+                write!(f, "start position ({}, {}) is inside a wall", x, y)
+            }
+            ConfigError::StartsOverlap(Coordinate { x, y }) => {
+                write!(f, "more than one snake starts at ({}, {})", x, y)
+            }
+            ConfigError::ObstacleOutOfBounds(Coordinate { x, y }) => {
+                write!(f, "wall at ({}, {}) is off the board", x, y)
+            }
+            ConfigError::ZeroLength => write!(f, "snake length must be at least 1"),
+            ConfigError::ZeroGrowthInterval => write!(f, "timed growth interval must be non-zero"),
+            ConfigError::ZeroTickInterval => write!(f, "tick interval must be non-zero"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use crate::level::Level;
+    use crate::{ConfigError, Coordinate, Direction, GameConfig, Growth, Speed};
+    use std::time::Duration;
+    use test_case::test_case;
+
+    #[test_case(0, 0)]
+    #[test_case(0, 10)]
+    #[test_case(10, 0)]
+    fn empty_board_is_rejected(width: u16, height: u16) {
+        assert_eq!(
+            GameConfig::new(width, height).validate(),
+            Err(ConfigError::EmptyBoard { width, height })
+        );
+    }
+
+    #[test_case(257, 256)]
+    #[test_case(65535, 65535)]
+    fn oversized_board_is_rejected(width: u16, height: u16) {
+        assert_eq!(
+            GameConfig::new(width, height).validate(),
+            Err(ConfigError::BoardTooLarge { width, height })
+        );
+        assert_eq!(GameConfig::new(256, 256).validate(), Ok(()));
+    }
+
+    #[test_case(-1, 0)]
+    #[test_case(0, -1)]
+    #[test_case(10, 0)]
+    #[test_case(0, 10)]
+    fn start_off_board_is_rejected(x: i32, y: i32) {
+        assert_eq!(
+            GameConfig::new(10, 10)
+                .start(Coordinate { x, y })
+                .validate(),
+            Err(ConfigError::StartOutOfBounds(Coordinate { x, y }))
+        );
+    }
+
+    #[test]
+    fn start_on_obstacle_is_rejected() {
+        assert_eq!(
+            GameConfig::new(10, 10)
+                .obstacles(HashSet::from([Coordinate { x: 5, y: 5 }]))
+                .validate(),
+            Err(ConfigError::StartOnObstacle(Coordinate { x: 5, y: 5 }))
+        );
+    }
+
+    #[test]
+    fn obstacle_off_board_is_rejected() {
+        assert_eq!(
+            GameConfig::new(10, 10)
+                .obstacles(HashSet::from([Coordinate { x: 10, y: 0 }]))
+                .validate(),
+            Err(ConfigError::ObstacleOutOfBounds(Coordinate { x: 10, y: 0 }))
+        );
+    }
+
+    #[test]
+    fn level_sets_size_start_and_obstacles() {
+        let level = Level::parse("#####\n#..S#\n#####").unwrap();
+        let config = GameConfig::from_level(&level);
+        assert_eq!((config.width, config.height), (5, 3));
+        assert_eq!(config.snakes[0].position, Coordinate { x: 3, y: 1 });
+        assert_eq!(config.obstacles, level.walls);
+        assert_eq!(config.validate(), Ok(()));
+    }
+
+    #[test]
+    fn zero_length_is_rejected() {
+        assert_eq!(
+            GameConfig::new(10, 10).length(0).validate(),
+            Err(ConfigError::ZeroLength)
+        );
+    }
+
+    #[test]
+    fn zero_growth_interval_is_rejected() {
+        assert_eq!(
+            GameConfig::new(10, 10)
+                .growth(Growth::Timed { every: 0, by: 3 })
+                .validate(),
+            Err(ConfigError::ZeroGrowthInterval)
+        );
+    }
+
+    #[test]
+    fn zero_tick_interval_is_rejected() {
+        assert_eq!(
+            GameConfig::new(10, 10)
+                .speed(Speed::constant(Duration::ZERO))
+                .validate(),
+            Err(ConfigError::ZeroTickInterval)
+        );
+    }
+
+    #[test]
+    fn defaults_are_valid() {
+        assert_eq!(GameConfig::new(1, 1).validate(), Ok(()));
+    }
+
+    #[test]
+    fn start_position_defaults_to_centre() {
+        assert_eq!(
+            GameConfig::new(30, 10).snakes[0].position,
+            Coordinate { x: 15, y: 5 }
+        );
+    }
+
+    #[test]
+    fn no_snakes_is_rejected() {
+        let mut config = GameConfig::new(10, 10);
+        config.snakes.clear();
+        assert_eq!(config.validate(), Err(ConfigError::NoSnakes));
+        let config = config
+            .start(Coordinate { x: 1, y: 1 })
+            .direction(Direction::North);
+        assert_eq!(config.validate(), Err(ConfigError::NoSnakes));
+    }
+
+    #[test]
+    fn overlapping_starts_are_rejected() {
+        assert_eq!(
+            GameConfig::new(10, 10)
+                .add_snake(Coordinate { x: 5, y: 5 }, Direction::North)
+                .validate(),
+            Err(ConfigError::StartsOverlap(Coordinate { x: 5, y: 5 }))
+        );
+    }
+
+    #[test_case(2)]
+    #[test_case(3)]
+    #[test_case(4)]
+    fn versus_places_distinct_valid_starts(players: usize) {
+        let config = GameConfig::versus(30, 20, players);
+        assert_eq!(config.snakes.len(), players);
+        assert_eq!(config.validate(), Ok(()));
+    }
+
+    #[test_case(0, 10)]
+    #[test_case(10, 0)]
+    fn versus_on_an_empty_board_is_an_error(width: u16, height: u16) {
+        assert_eq!(
+            GameConfig::versus(width, height, 2).validate(),
+            Err(ConfigError::EmptyBoard { width, height })
+        );
+    }
+}
@@ -0,0 +1,499 @@
+//! A gym-style environment for training agents against the real rules.
+//!
+//! The agent controls the first snake with relative [`Action`]s; any other
+//! snakes in the config carry straight on. Observations are flat `f32`
+//! vectors built from the chosen [`Encoding`]s, in order.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{
+    ConfigError, Coordinate, Direction, Game, GameConfig, GameEvent, GameOverReason, Input, Turn,
+    Walls,
+};
+
+pub type Observation = Vec<f32>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Straight,
+    Left,
+    Right,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    /// The board as four `width * height` planes, one after another: cells
+    /// that would kill the snake (walls and other snakes), its own body,
+    /// its head, and food.
+    Grid,
+    /// Eight rays cast from the head, starting straight ahead and going
+    /// clockwise. Each gives `1 / distance` to the nearest deadly cell and
+    /// whether food lies before it.
+    Rays,
+    /// The nearest food relative to the head, as distance ahead and to the
+    /// right, scaled by the board size.
+    Food,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct UnknownEncoding(pub String);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rewards {
+    pub food: f32,
+    pub death: f32,
+    /// Every step, usually a small negative to discourage dawdling.
+    pub step: f32,
+    /// Times the change in distance to the nearest food: positive for
+    /// getting closer, negative for moving away.
+    pub closer: f32,
+}
+
+pub struct Env {
+    /// Private so it stays the config `new` checked.
+    config: GameConfig,
+    pub encodings: Vec<Encoding>,
+    pub rewards: Rewards,
+    /// Episodes end after this many steps without eating.
+    pub max_idle: u32,
+    game: Game,
+    idle: u32,
+}
+
+impl Action {
+    pub const ALL: [Action; 3] = [Action::Straight, Action::Left, Action::Right];
+
+    fn input(&self) -> Option<Input> {
+        match self {
+            Action::Straight => None,
+            Action::Left => Some(Input::Turn(Turn::Left)),
+            Action::Right => Some(Input::Turn(Turn::Right)),
+        }
+    }
+}
+
+impl FromStr for Encoding {
+    type Err = UnknownEncoding;
+
+    fn from_str(s: &str) -> Result<Encoding, UnknownEncoding> {
+        match s {
+            "grid" => Ok(Encoding::Grid),
+            "rays" => Ok(Encoding::Rays),
+            "food" => Ok(Encoding::Food),
+            _ => Err(UnknownEncoding(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for UnknownEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unknown encoding {:?}, expected grid, rays or food",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for UnknownEncoding {}
+
+impl Default for Rewards {
+    fn default() -> Rewards {
+        Rewards {
+            food: 1.0,
+            death: -1.0,
+            step: 0.0,
+            closer: 0.0,
+        }
+    }
+}
+
+impl Env {
+    pub fn new(config: GameConfig) -> Result<Env, ConfigError> {
+        Ok(Env {
+            game: Game::new(&config)?,
+            config,
+            encodings: vec![Encoding::Rays, Encoding::Food],
+            rewards: Rewards::default(),
+            max_idle: 1000,
+            idle: 0,
+        })
+    }
+
+    pub fn encodings(mut self, encodings: Vec<Encoding>) -> Env {
+        self.encodings = encodings;
+        self
+    }
+
+    pub fn rewards(mut self, rewards: Rewards) -> Env {
+        self.rewards = rewards;
+        self
+    }
+
+    pub fn max_idle(mut self, max_idle: u32) -> Env {
+        self.max_idle = max_idle;
+        self
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    pub fn config(&self) -> &GameConfig {
+        &self.config
+    }
+
+    /// Length of every observation with the current encodings.
+    pub fn observation_size(&self) -> usize {
+        let cells = usize::from(self.config.width) * usize::from(self.config.height);
+        self.encodings
+            .iter()
+            .map(|e| match e {
+                Encoding::Grid => 4 * cells,
+                Encoding::Rays => 16,
+                Encoding::Food => 2,
+            })
+            .sum()
+    }
+
+    /// Starts a new episode.
+    pub fn reset(&mut self, seed: u64) -> Observation {
+        // The config was checked when the environment was made, and can't
+        // have changed since; a seed never makes it invalid.
+        self.game = Game::new(&self.config.clone().seed(seed)).expect("invalid config");
+        self.idle = 0;
+        self.observe()
+    }
+
+    pub fn step(&mut self, action: Action) -> (Observation, f32, bool) {
+        // Other snakes can play on once the agent's is out, but its episode
+        // is over.
+        if self.game.game_over || !self.game.snakes[0].alive {
+            return (self.observe(), 0.0, true);
+        }
+        let before = self.food_distance();
+        if let Some(input) = action.input() {
+            self.game.steer(0, &input);
+        }
+        let events = self.game.advance();
+
+        let mut reward = self.rewards.step;
+        if !self.game.snakes[0].alive {
+            reward += self.rewards.death;
+        } else if events
+            .iter()
+            .any(|e| matches!(e, GameEvent::AteFood { id: 0, .. }))
+        {
+            reward += self.rewards.food;
+            self.idle = 0;
+        } else {
+            self.idle += 1;
+            if let (Some(before), Some(after)) = (before, self.food_distance()) {
+                reward += self.rewards.closer * (before - after) as f32;
+            }
+        }
+        if self.idle >= self.max_idle {
+            self.game.end(GameOverReason::Timeout);
+        }
+        let done = !self.game.snakes[0].alive || self.game.game_over;
+        (self.observe(), reward, done)
+    }
+
+    fn observe(&self) -> Observation {
+        let mut observation = Vec::with_capacity(self.observation_size());
+        for encoding in &self.encodings {
+            match encoding {
+                Encoding::Grid => self.grid(&mut observation),
+                Encoding::Rays => self.rays(&mut observation),
+                Encoding::Food => self.food(&mut observation),
+            }
+        }
+        observation
+    }
+
+    fn head(&self) -> Option<&Coordinate> {
+        self.game.snakes[0].head()
+    }
+
+    /// Whether moving into `c` would kill the snake.
+    fn deadly(&self, c: &Coordinate) -> bool {
+        c.out_of_bounds(&self.game)
+            || self.game.obstacles.contains(c)
+            || self.game.active_cells.contains(c)
+    }
+
+    /// Ahead and right unit vectors for the way the snake is heading.
+    fn frame(&self) -> ((i32, i32), (i32, i32)) {
+        let ahead = match self.game.snakes[0].direction() {
+            Direction::North => (0, -1),
+            Direction::South => (0, 1),
+            Direction::East => (1, 0),
+            Direction::West => (-1, 0),
+        };
+        (ahead, (-ahead.1, ahead.0))
+    }
+
+    fn grid(&self, observation: &mut Observation) {
+        let (w, h) = (i32::from(self.game.width), i32::from(self.game.height));
+        let cells = || (0..h).flat_map(move |y| (0..w).map(move |x| Coordinate { x, y }));
+        let snake = &self.game.snakes[0];
+        let flag = |b: bool| if b { 1.0 } else { 0.0 };
+        observation.extend(cells().map(|c| {
+            flag(
+                self.game.obstacles.contains(&c)
+                    || (self.game.active_cells.contains(&c) && !snake.body.contains(&c)),
+            )
+        }));
+        observation.extend(cells().map(|c| flag(snake.body.contains(&c))));
+        observation.extend(cells().map(|c| flag(self.head() == Some(&c))));
+        observation.extend(cells().map(|c| flag(self.game.food.contains(&c))));
+    }
+
+    fn rays(&self, observation: &mut Observation) {
+        let head = match self.head() {
+            Some(head) => head.clone(),
+            None => return observation.extend([0.0; 16]),
+        };
+        let (a, r) = self.frame();
+        let rays = [
+            a,
+            (a.0 + r.0, a.1 + r.1),
+            r,
+            (r.0 - a.0, r.1 - a.1),
+            (-a.0, -a.1),
+            (-a.0 - r.0, -a.1 - r.1),
+            (-r.0, -r.1),
+            (a.0 - r.0, a.1 - r.1),
+        ];
+        // On a wrapping board a ray could go round forever.
+        let limit = i32::from(self.game.width.max(self.game.height));
+        for (dx, dy) in rays {
+            let mut c = head.clone();
+            let mut food = 0.0;
+            let mut distance = 1;
+            loop {
+                c = Coordinate {
+                    x: c.x + dx,
+                    y: c.y + dy,
+                };
+                if self.config.walls == Walls::Wrap {
+                    c = c.wrap(&self.game);
+                }
+                if self.deadly(&c) || distance >= limit {
+                    break;
+                }
+                if self.game.food.contains(&c) {
+                    food = 1.0;
+                }
+                distance += 1;
+            }
+            observation.push(1.0 / distance as f32);
+            observation.push(food);
+        }
+    }
+
+    fn food(&self, observation: &mut Observation) {
+        let (head, food) = match (self.head(), self.nearest_food()) {
+            (Some(head), Some(food)) => (head, food),
+            _ => return observation.extend([0.0, 0.0]),
+        };
+        let (a, r) = self.frame();
+        let (dx, dy) = self.offset(head, food);
+        let size = f32::from(self.game.width.max(self.game.height));
+        observation.push((dx * a.0 + dy * a.1) as f32 / size);
+        observation.push((dx * r.0 + dy * r.1) as f32 / size);
+    }
+
+    /// How far `to` is from `from` along each axis, the shorter way round
+    /// on a wrapping board.
+    fn offset(&self, from: &Coordinate, to: &Coordinate) -> (i32, i32) {
+        let (dx, dy) = (to.x - from.x, to.y - from.y);
+        if self.config.walls != Walls::Wrap {
+            return (dx, dy);
+        }
+        let wrap = |d: i32, size: u16| {
+            let size = i32::from(size);
+            if 2 * d.abs() > size {
+                d - d.signum() * size
+            } else {
+                d
+            }
+        };
+        (wrap(dx, self.game.width), wrap(dy, self.game.height))
+    }
+
+    fn distance(&self, from: &Coordinate, to: &Coordinate) -> i32 {
+        let (dx, dy) = self.offset(from, to);
+        dx.abs() + dy.abs()
+    }
+
+    fn nearest_food(&self) -> Option<&Coordinate> {
+        let head = self.head()?;
+        self.game
+            .food
+            .iter()
+            .min_by_key(|f| (self.distance(head, f), f.x, f.y))
+    }
+
+    fn food_distance(&self) -> Option<i32> {
+        let (head, food) = (self.head()?, self.nearest_food()?);
+        Some(self.distance(head, food))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use crate::env::{Action, Encoding, Env, Rewards};
+    use crate::{ConfigError, Coordinate, Direction, GameConfig, GameOverReason, Walls};
+    use test_case::test_case;
+
+    fn env(encodings: Vec<Encoding>) -> Env {
+        Env::new(GameConfig::new(10, 10))
+            .unwrap()
+            .encodings(encodings)
+    }
+
+    #[test_case(vec![Encoding::Grid], 400)]
+    #[test_case(vec![Encoding::Rays], 16)]
+    #[test_case(vec![Encoding::Food], 2)]
+    #[test_case(vec![Encoding::Rays, Encoding::Food], 18)]
+    fn observations_match_their_size(encodings: Vec<Encoding>, size: usize) {
+        let mut env = env(encodings);
+        assert_eq!(env.observation_size(), size);
+        assert_eq!(env.reset(1).len(), size);
+        assert_eq!(env.step(Action::Left).0.len(), size);
+    }
+
+    #[test]
+    fn grid_marks_head_and_food() {
+        let mut env = env(vec![Encoding::Grid]);
+        let observation = env.reset(1);
+        let food = env.game().food.iter().next().unwrap().clone();
+        let plane = |p: usize, x: i32, y: i32| observation[p * 100 + (y * 10 + x) as usize];
+        assert_eq!(plane(2, 5, 5), 1.0);
+        assert_eq!(plane(1, 5, 5), 1.0);
+        assert_eq!(plane(3, food.x, food.y), 1.0);
+        assert_eq!(observation[200..300].iter().sum::<f32>(), 1.0);
+    }
+
+    #[test]
+    fn rays_see_walls_relative_to_heading() {
+        let mut env = env(vec![Encoding::Rays]);
+        env.reset(1);
+        env.step(Action::Left);
+        let observation = env.step(Action::Straight).0;
+        // Heading north from (5, 3): the wall is four cells ahead, five to
+        // the right and six to the left.
+        assert_eq!(observation[0], 1.0 / 4.0);
+        assert_eq!(observation[4], 1.0 / 5.0);
+        assert_eq!(observation[12], 1.0 / 6.0);
+    }
+
+    #[test]
+    fn food_is_relative_to_heading() {
+        let mut env = env(vec![Encoding::Food]);
+        env.reset(1);
+        env.game.food = HashSet::from([Coordinate { x: 8, y: 3 }]);
+        // Heading east from (5, 5): three ahead, two to the left.
+        assert_eq!(env.observe(), vec![0.3, -0.2]);
+        env.step(Action::Left);
+        // Heading north from (5, 4): one ahead, three to the right.
+        assert_eq!(env.observe(), vec![0.1, 0.3]);
+    }
+
+    #[test]
+    fn eating_and_dying_are_rewarded() {
+        let mut env = env(vec![Encoding::Food]);
+        env.reset(1);
+        env.game.food = HashSet::from([Coordinate { x: 6, y: 5 }]);
+        assert_eq!(env.step(Action::Straight).1, 1.0);
+        let (_, reward, done) = (0..3).map(|_| env.step(Action::Straight)).last().unwrap();
+        assert!(!done);
+        assert_eq!(reward, 0.0);
+        let (_, reward, done) = env.step(Action::Straight);
+        assert!(done);
+        assert_eq!(reward, -1.0);
+    }
+
+    #[test]
+    fn episode_ends_when_the_agent_is_out_while_others_play_on() {
+        let mut env = Env::new(
+            GameConfig::new(10, 10)
+                .add_snake(Coordinate { x: 1, y: 1 }, Direction::South)
+                .add_snake(Coordinate { x: 3, y: 1 }, Direction::South),
+        )
+        .unwrap()
+        .encodings(vec![]);
+        env.reset(1);
+        env.game.food.clear();
+        let (_, reward, done) = (0..5).map(|_| env.step(Action::Straight)).last().unwrap();
+        assert!(done);
+        assert_eq!(reward, -1.0);
+        assert!(!env.game().game_over);
+        let ticks = env.game().ticks;
+        assert_eq!(env.step(Action::Straight), (vec![], 0.0, true));
+        assert_eq!(env.game().ticks, ticks);
+    }
+
+    #[test]
+    fn shaping_rewards_getting_closer() {
+        let mut env = env(vec![]).rewards(Rewards {
+            step: -0.01,
+            closer: 0.1,
+            ..Rewards::default()
+        });
+        env.reset(1);
+        env.game.food = HashSet::from([Coordinate { x: 5, y: 1 }]);
+        assert_eq!(env.step(Action::Straight).1, -0.11);
+        assert_eq!(env.step(Action::Left).1, 0.09);
+    }
+
+    #[test]
+    fn food_is_found_the_short_way_round_when_wrapping() {
+        let mut env = Env::new(GameConfig::new(10, 10).walls(Walls::Wrap))
+            .unwrap()
+            .encodings(vec![Encoding::Food])
+            .rewards(Rewards {
+                step: -0.01,
+                closer: 0.1,
+                ..Rewards::default()
+            });
+        env.reset(1);
+        env.game.food = HashSet::from([Coordinate { x: 1, y: 5 }]);
+        // Heading east from (5, 5): four behind.
+        assert_eq!(env.observe(), vec![-0.4, 0.0]);
+        let rewards: Vec<f32> = (0..5).map(|_| env.step(Action::Straight).1).collect();
+        // Further at first, then closer the other way, across the edge.
+        assert_eq!(rewards, vec![-0.11, 0.09, 0.09, 0.09, 0.09]);
+        assert_eq!(env.observe(), vec![0.1, 0.0]);
+    }
+
+    #[test]
+    fn idle_episodes_end() {
+        let mut env = Env::new(GameConfig::new(10, 10).food(0))
+            .unwrap()
+            .max_idle(3);
+        env.reset(1);
+        let dones: Vec<bool> = (0..3).map(|_| env.step(Action::Straight).2).collect();
+        assert_eq!(dones, vec![false, false, true]);
+        assert_eq!(env.game().game_over_reason, Some(GameOverReason::Timeout));
+    }
+
+    #[test]
+    fn reset_starts_over_with_the_seed() {
+        let mut env = env(vec![Encoding::Grid]);
+        let first = env.reset(9);
+        env.step(Action::Left);
+        assert_eq!(env.reset(9), first);
+        assert_eq!(env.game().ticks, 0);
+    }
+
+    #[test]
+    fn invalid_config_is_an_error() {
+        let error = Env::new(GameConfig::new(10, 10).length(0)).err();
+        assert_eq!(error, Some(ConfigError::ZeroLength));
+    }
+}
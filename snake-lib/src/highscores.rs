@@ -0,0 +1,222 @@
+//! A table of the best finished games.
+//!
+//! The table itself is plain data; front ends decide where it lives by
+//! implementing [`HighScoreStore`] (a file for the console, `localStorage`
+//! on the web). Enable the `serde` feature to serialize it.
+
+use crate::{Game, GameConfig, SnakeId};
+
+pub const DEFAULT_CAPACITY: usize = 10;
+
+/// How many entries front ends list when a game ends.
+pub const SHOWN: usize = 5;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HighScore {
+    pub name: String,
+    pub score: u32,
+    pub length: usize,
+    /// How many ticks the game lasted.
+    pub ticks: u32,
+    pub seed: u64,
+    pub config: GameConfig,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HighScores {
+    pub capacity: usize,
+    /// Best first; ties keep the earlier entry ahead.
+    pub entries: Vec<HighScore>,
+}
+
+pub trait HighScoreStore {
+    type Error;
+
+    /// An empty table if nothing has been saved yet.
+    fn load(&self) -> Result<HighScores, Self::Error>;
+    fn save(&self, scores: &HighScores) -> Result<(), Self::Error>;
+}
+
+/// Adds player 0 of a finished game to the table in `store`, saving it only
+/// if the entry made the table. Returns the table and the entry's rank.
+pub fn record<S: HighScoreStore>(
+    store: &S,
+    game: &Game,
+    name: &str,
+) -> Result<(HighScores, Option<usize>), S::Error> {
+    let mut scores = store.load()?;
+    let rank = scores.insert(HighScore::from_game(game, 0, name));
+    if rank.is_some() {
+        store.save(&scores)?;
+    }
+    Ok((scores, rank))
+}
+
+impl HighScore {
+    pub fn from_game(game: &Game, id: SnakeId, name: &str) -> HighScore {
+        let snake = &game.snakes[id];
+        HighScore {
+            name: name.to_string(),
+            score: snake.score().total(),
+            length: snake.len(),
+            ticks: game.ticks,
+            seed: game.config.seed,
+            config: game.config.clone(),
+        }
+    }
+}
+
+impl Default for HighScores {
+    fn default() -> HighScores {
+        HighScores::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl HighScores {
+    pub fn new(capacity: usize) -> HighScores {
+        HighScores {
+            capacity,
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn qualifies(&self, score: u32) -> bool {
+        self.entries.len() < self.capacity
+            || self.entries.last().is_some_and(|last| score > last.score)
+    }
+
+    /// Adds an entry if it makes the table, returning its rank (zero-based).
+    pub fn insert(&mut self, entry: HighScore) -> Option<usize> {
+        if !self.qualifies(entry.score) {
+            return None;
+        }
+        let rank = self
+            .entries
+            .iter()
+            .position(|e| entry.score > e.score)
+            .unwrap_or(self.entries.len());
+        self.entries.insert(rank, entry);
+        self.entries.truncate(self.capacity);
+        Some(rank)
+    }
+
+    pub fn top(&self, n: usize) -> &[HighScore] {
+        &self.entries[..n.min(self.entries.len())]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::highscores::{record, HighScore, HighScoreStore, HighScores};
+    use crate::{Coordinate, Game, GameConfig};
+    use std::cell::{Cell, RefCell};
+    use std::collections::HashSet;
+
+    #[derive(Default)]
+    struct MemoryStore {
+        scores: RefCell<HighScores>,
+        saves: Cell<u32>,
+    }
+
+    impl HighScoreStore for MemoryStore {
+        type Error = ();
+
+        fn load(&self) -> Result<HighScores, ()> {
+            Ok(self.scores.borrow().clone())
+        }
+
+        fn save(&self, scores: &HighScores) -> Result<(), ()> {
+            *self.scores.borrow_mut() = scores.clone();
+            self.saves.set(self.saves.get() + 1);
+            Ok(())
+        }
+    }
+
+    fn entry(name: &str, score: u32) -> HighScore {
+        HighScore {
+            name: name.to_string(),
+            score,
+            length: 4,
+            ticks: 100,
+            seed: 1,
+            config: GameConfig::new(20, 20),
+        }
+    }
+
+    #[test]
+    fn entries_are_kept_best_first() {
+        let mut scores = HighScores::new(5);
+        assert_eq!(scores.insert(entry("a", 10)), Some(0));
+        assert_eq!(scores.insert(entry("b", 30)), Some(0));
+        assert_eq!(scores.insert(entry("c", 20)), Some(1));
+        let names: Vec<&str> = scores.entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["b", "c", "a"]);
+    }
+
+    #[test]
+    fn ties_rank_below_earlier_entries() {
+        let mut scores = HighScores::new(5);
+        scores.insert(entry("first", 10));
+        assert_eq!(scores.insert(entry("second", 10)), Some(1));
+    }
+
+    #[test]
+    fn full_table_drops_lowest() {
+        let mut scores = HighScores::new(2);
+        scores.insert(entry("a", 10));
+        scores.insert(entry("b", 20));
+        assert!(!scores.qualifies(10));
+        assert_eq!(scores.insert(entry("c", 5)), None);
+        assert_eq!(scores.insert(entry("d", 15)), Some(1));
+        let names: Vec<&str> = scores.entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["b", "d"]);
+    }
+
+    #[test]
+    fn top_is_clamped_to_table_size() {
+        let mut scores = HighScores::new(5);
+        scores.insert(entry("a", 10));
+        assert_eq!(scores.top(3).len(), 1);
+    }
+
+    #[test]
+    fn entry_from_finished_game() {
+        let mut game = Game::new(&GameConfig::new(20, 20).seed(99)).unwrap();
+        game.food = HashSet::from([Coordinate { x: 11, y: 10 }]);
+        while !game.game_over {
+            game.advance();
+        }
+        let entry = HighScore::from_game(&game, 0, "ann");
+        assert_eq!(entry.name, "ann");
+        assert_eq!(entry.score, game.score(0).total());
+        assert!(entry.score > 0);
+        assert_eq!(entry.length, game.snakes[0].len());
+        assert_eq!(entry.ticks, game.ticks);
+        assert_eq!(entry.seed, 99);
+        assert_eq!(entry.config, game.config);
+    }
+
+    #[test]
+    fn record_saves_only_entries_that_make_the_table() {
+        let store = MemoryStore::default();
+        store.scores.replace(HighScores::new(1));
+        let game = Game::new(&GameConfig::new(20, 20)).unwrap();
+        let (scores, rank) = record(&store, &game, "ann").unwrap();
+        assert_eq!(rank, Some(0));
+        assert_eq!(scores.entries.len(), 1);
+        assert_eq!(record(&store, &game, "bob").unwrap().1, None);
+        assert_eq!(store.saves.get(), 1);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn table_round_trips_through_json() {
+        let mut scores = HighScores::new(3);
+        scores.insert(entry("a", 10));
+        scores.insert(entry("b", 20));
+        let json = serde_json::to_string(&scores).unwrap();
+        assert_eq!(serde_json::from_str::<HighScores>(&json).unwrap(), scores);
+    }
+}
@@ -0,0 +1,296 @@
+//! Rewinding recent ticks. Each call to `Game::advance` records just enough
+//! to undo itself, and the game keeps the last `GameConfig::rewind` of these.
+
+use crate::{Coordinate, Direction, Game, GameEvent, GameOverReason, Rng, Score, SnakeId};
+
+/// What one tick changed.
+pub(crate) struct Tick {
+    snakes: Vec<SnakeTick>,
+    eaten: Vec<Coordinate>,
+    spawned: Vec<Coordinate>,
+    rng: Rng,
+    game_over: bool,
+    game_over_reason: Option<GameOverReason>,
+    winner: Option<SnakeId>,
+}
+
+/// A live snake as it was when the tick started.
+struct SnakeTick {
+    id: SnakeId,
+    /// A tick leaves every snake steering the way it moved, so this restores
+    /// its direction too.
+    heading: Direction,
+    growth: u32,
+    score: Score,
+    alive: bool,
+    /// Whether the tick added a head.
+    moved: bool,
+    /// The tail it dropped, if any.
+    tail: Option<Coordinate>,
+}
+
+impl Tick {
+    /// Notes the parts of `game` a tick may change, before it runs.
+    pub(crate) fn start(game: &Game) -> Tick {
+        Tick {
+            snakes: game
+                .snakes
+                .iter()
+                .filter(|s| s.alive)
+                .map(|s| SnakeTick {
+                    id: s.id,
+                    heading: s.heading,
+                    growth: s.growth,
+                    score: s.score,
+                    alive: s.alive,
+                    moved: false,
+                    tail: None,
+                })
+                .collect(),
+            eaten: Vec::new(),
+            spawned: Vec::new(),
+            rng: game.rng.clone(),
+            game_over: game.game_over,
+            game_over_reason: game.game_over_reason.clone(),
+            winner: game.winner,
+        }
+    }
+
+    /// Fills in what the tick did: the tails each snake dropped, crashed or
+    /// not, and the rest from its events.
+    pub(crate) fn finish(
+        &mut self,
+        tails: Vec<(SnakeId, Option<Coordinate>)>,
+        events: &[GameEvent],
+    ) {
+        for (id, tail) in tails {
+            if let Some(snake) = self.snakes.iter_mut().find(|s| s.id == id) {
+                snake.tail = tail;
+            }
+        }
+        for event in events {
+            match event {
+                GameEvent::Moved { id, .. } => {
+                    if let Some(snake) = self.snakes.iter_mut().find(|s| s.id == *id) {
+                        snake.moved = true;
+                    }
+                }
+                GameEvent::AteFood { food, spawned, .. } => {
+                    self.eaten.push(food.clone());
+                    self.spawned.extend(spawned.clone());
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn undo(self, game: &mut Game) {
+        // Take every new head off before putting tails back, as a head may
+        // have moved into a cell another snake's tail left.
+        for s in self.snakes.iter().filter(|s| s.moved) {
+            let head = game.snakes[s.id].body.pop_front().unwrap();
+            game.active_cells.remove(&head);
+        }
+        for s in self.snakes {
+            let snake = &mut game.snakes[s.id];
+            if let Some(tail) = s.tail {
+                game.active_cells.insert(tail.clone());
+                snake.body.push_back(tail);
+            }
+            snake.direction = s.heading;
+            snake.heading = s.heading;
+            snake.growth = s.growth;
+            snake.score = s.score;
+            snake.alive = s.alive;
+        }
+        for c in &self.spawned {
+            game.food.remove(c);
+        }
+        game.food.extend(self.eaten);
+        game.rng = self.rng;
+        game.ticks -= 1;
+        game.game_over = self.game_over;
+        game.game_over_reason = self.game_over_reason;
+        game.winner = self.winner;
+    }
+}
+
+impl Game {
+    /// Undoes up to `ticks` of the most recent ticks, even after the game has
+    /// ended, returning how many were undone.
+    pub fn rewind(&mut self, ticks: usize) -> usize {
+        let mut undone = 0;
+        while undone < ticks {
+            match self.history.pop_back() {
+                Some(tick) => tick.undo(self),
+                None => break,
+            }
+            undone += 1;
+        }
+        undone
+    }
+
+    /// How many ticks `rewind` can currently undo.
+    pub fn rewindable(&self) -> usize {
+        self.history.len()
+    }
+
+    pub(crate) fn remember(&mut self, tick: Tick) {
+        if self.config.rewind == 0 {
+            return;
+        }
+        self.history.push_back(tick);
+        while self.history.len() > self.config.rewind {
+            self.history.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use crate::{Coordinate, Direction, Game, GameConfig, GameOverReason, Growth, Input, Turn};
+    use test_case::test_case;
+
+    /// Everything `rewind` has to put back.
+    fn state(game: &Game) -> String {
+        let mut food: Vec<_> = game.food.iter().map(|c| (c.x, c.y)).collect();
+        food.sort();
+        let mut active: Vec<_> = game.active_cells.iter().map(|c| (c.x, c.y)).collect();
+        active.sort();
+        format!(
+            "{:?} {:?} {:?} {} {} {:?} {:?} {:?}",
+            game.snakes,
+            food,
+            active,
+            game.ticks,
+            game.game_over,
+            game.game_over_reason,
+            game.winner,
+            game.rng
+        )
+    }
+
+    #[test]
+    fn rewind_restores_earlier_ticks_exactly() {
+        let mut game = Game::new(&GameConfig::new(10, 10).seed(7).food(3)).unwrap();
+        let mut states = vec![state(&game)];
+        for turn in [None, Some(Turn::Left), None, Some(Turn::Left), None, None] {
+            if let Some(turn) = turn {
+                game.turn(0, &turn);
+            }
+            game.advance();
+            states.push(state(&game));
+        }
+        for expected in states.iter().rev().skip(1) {
+            assert_eq!(game.rewind(1), 1);
+            assert_eq!(&state(&game), expected);
+        }
+        assert_eq!(game.rewind(1), 0);
+    }
+
+    #[test]
+    fn rewind_undoes_eating() {
+        let mut game = Game::new(&GameConfig::new(20, 20)).unwrap();
+        game.food = HashSet::from([Coordinate { x: 11, y: 10 }]);
+        let before = state(&game);
+        game.advance();
+        assert_ne!(game.food, HashSet::from([Coordinate { x: 11, y: 10 }]));
+        game.rewind(1);
+        assert_eq!(state(&game), before);
+    }
+
+    // Food eaten by one snake used to be replaced before the other moved;
+    // with seed 32 that left food behind on (7, 5) after rewinding.
+    #[test_case(32)]
+    #[test_case(44)]
+    fn rewind_after_one_of_two_snakes_eats(seed: u64) {
+        let mut game = Game::new(
+            &GameConfig::new(9, 9)
+                .seed(seed)
+                .start(Coordinate { x: 4, y: 5 })
+                .direction(Direction::East)
+                .add_snake(Coordinate { x: 8, y: 5 }, Direction::West),
+        )
+        .unwrap();
+        game.food = HashSet::from([Coordinate { x: 5, y: 5 }]);
+        let before = state(&game);
+        game.advance();
+        assert!(!game.food.contains(&Coordinate { x: 5, y: 5 }));
+        assert_eq!(game.rewind(1), 1);
+        assert_eq!(state(&game), before);
+        assert_eq!(game.food, HashSet::from([Coordinate { x: 5, y: 5 }]));
+    }
+
+    #[test]
+    fn rewind_brings_a_crashed_snake_back() {
+        let mut game = Game::new(&GameConfig::new(20, 20).food(0)).unwrap();
+        while !game.game_over {
+            game.advance();
+        }
+        assert_eq!(game.rewind(3), 3);
+        assert!(!game.game_over);
+        assert_eq!(game.game_over_reason, None);
+        assert!(game.snakes[0].alive);
+        assert_eq!(game.snakes[0].head(), Some(&Coordinate { x: 17, y: 10 }));
+        game.steer(0, &Input::Direction(Direction::South));
+        for _ in 0..3 {
+            game.advance();
+        }
+        assert!(!game.game_over);
+    }
+
+    #[test]
+    fn rewind_restores_growth_and_direction() {
+        let mut game = Game::new(
+            &GameConfig::new(20, 20)
+                .food(0)
+                .growth(Growth::Timed { every: 2, by: 5 }),
+        )
+        .unwrap();
+        game.advance();
+        game.steer(0, &Input::Direction(Direction::North));
+        game.advance();
+        game.rewind(1);
+        assert_eq!(game.snakes[0].growth, 2);
+        assert_eq!(game.snakes[0].direction(), Direction::East);
+    }
+
+    #[test]
+    fn rewind_restores_both_snakes_after_a_head_on_crash() {
+        let mut game = Game::new(
+            &GameConfig::new(20, 20)
+                .start(Coordinate { x: 5, y: 5 })
+                .add_snake(Coordinate { x: 8, y: 5 }, Direction::West)
+                .food(0),
+        )
+        .unwrap();
+        let before = state(&game);
+        while !game.game_over {
+            game.advance();
+        }
+        assert_eq!(game.game_over_reason, Some(GameOverReason::OtherSnake(1)));
+        game.rewind(game.rewindable());
+        assert_eq!(state(&game), before);
+    }
+
+    #[test]
+    fn history_is_bounded() {
+        let mut game = Game::new(&GameConfig::new(40, 40).food(0).rewind(5)).unwrap();
+        for _ in 0..8 {
+            game.advance();
+        }
+        assert_eq!(game.rewindable(), 5);
+        assert_eq!(game.rewind(10), 5);
+        assert_eq!(game.ticks, 3);
+    }
+
+    #[test]
+    fn zero_disables_history() {
+        let mut game = Game::new(&GameConfig::new(20, 20).rewind(0)).unwrap();
+        game.advance();
+        assert_eq!(game.rewind(1), 0);
+        assert_eq!(game.ticks, 1);
+    }
+}
@@ -0,0 +1,216 @@
+//! Plain-text level maps.
+//!
+//! Each line is a row of the board: `#` is a wall, `.` is floor and `S`
+//! marks where the snake starts. Leading and trailing blank lines are
+//! ignored; every other row must be the same width.
+//!
+//! ```text
+//! ##########
+//! #........#
+//! #..S.....#
+//! #....##..#
+//! ##########
+//! ```
+
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::Coordinate;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Level {
+    pub width: u16,
+    pub height: u16,
+    pub walls: HashSet<Coordinate>,
+    pub start: Option<Coordinate>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum LevelError {
+    Empty,
+    TooLarge,
+    RaggedRow {
+        row: usize,
+        expected: usize,
+        found: usize,
+    },
+    UnknownTile {
+        row: usize,
+        column: usize,
+        tile: char,
+    },
+    MultipleStarts {
+        first: Coordinate,
+        second: Coordinate,
+    },
+}
+
+impl Level {
+    pub fn parse(text: &str) -> Result<Level, LevelError> {
+        let rows: Vec<&str> = text.lines().map(|l| l.trim_end_matches('\r')).collect();
+        let first = rows.iter().position(|r| !r.trim().is_empty());
+        let last = rows.iter().rposition(|r| !r.trim().is_empty());
+        let rows = match (first, last) {
+            (Some(first), Some(last)) => &rows[first..=last],
+            _ => return Err(LevelError::Empty),
+        };
+
+        let width = rows[0].chars().count();
+        let mut walls = HashSet::new();
+        let mut start: Option<Coordinate> = None;
+        for (y, row) in rows.iter().enumerate() {
+            let found = row.chars().count();
+            if found != width {
+                return Err(LevelError::RaggedRow {
+                    row: y,
+                    expected: width,
+                    found,
+                });
+            }
+            for (x, tile) in row.chars().enumerate() {
+                let c = Coordinate {
+                    x: x as i32,
+                    y: y as i32,
+                };
+                match tile {
+                    '#' => {
+                        walls.insert(c);
+                    }
+                    'S' => match start {
+                        Some(first) => return Err(LevelError::MultipleStarts { first, second: c }),
+                        None => start = Some(c),
+                    },
+                    '.' => {}
+                    _ => {
+                        return Err(LevelError::UnknownTile {
+                            row: y,
+                            column: x,
+                            tile,
+                        })
+                    }
+                }
+            }
+        }
+
+        Ok(Level {
+            width: width.try_into().map_err(|_| LevelError::TooLarge)?,
+            height: rows.len().try_into().map_err(|_| LevelError::TooLarge)?,
+            walls,
+            start,
+        })
+    }
+}
+
+impl FromStr for Level {
+    type Err = LevelError;
+
+    fn from_str(s: &str) -> Result<Level, LevelError> {
+        Level::parse(s)
+    }
+}
+
+impl fmt::Display for LevelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LevelError::Empty => write!(f, "level is empty"),
+            LevelError::TooLarge => write!(f, "level is too large"),
+            LevelError::RaggedRow {
+                row,
+                expected,
+                found,
+            } => write!(
+                f,
+                "row {} is {} tiles wide, expected {}",
+                row, found, expected
+            ),
+            LevelError::UnknownTile { row, column, tile } => {
+                write!(
+                    f,
+                    "unknown tile {:?} at row {}, column {}",
+                    tile, row, column
+                )
+            }
+            LevelError::MultipleStarts { first, second } => write!(
+                f,
+                "more than one start: ({}, {}) and ({}, {})",
+                first.x, first.y, second.x, second.y
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LevelError {}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use crate::level::{Level, LevelError};
+    use crate::Coordinate;
+
+    #[test]
+    fn parses_walls_start_and_size() {
+        let level = Level::parse("####\n#S.#\n####\n").unwrap();
+        assert_eq!(level.width, 4);
+        assert_eq!(level.height, 3);
+        assert_eq!(level.start, Some(Coordinate { x: 1, y: 1 }));
+        assert_eq!(level.walls.len(), 10);
+        assert!(!level.walls.contains(&Coordinate { x: 2, y: 1 }));
+    }
+
+    #[test]
+    fn start_is_optional() {
+        let level = Level::parse("...\n.#.\n").unwrap();
+        assert_eq!(level.start, None);
+        assert_eq!(level.walls, HashSet::from([Coordinate { x: 1, y: 1 }]));
+    }
+
+    #[test]
+    fn surrounding_blank_lines_and_crlf_are_ignored() {
+        let level = Level::parse("\n\r\n#.\r\n.S\r\n\n").unwrap();
+        assert_eq!(level.width, 2);
+        assert_eq!(level.height, 2);
+        assert_eq!(level.start, Some(Coordinate { x: 1, y: 1 }));
+    }
+
+    #[test]
+    fn empty_level_is_rejected() {
+        assert_eq!(Level::parse("\n  \n"), Err(LevelError::Empty));
+    }
+
+    #[test]
+    fn ragged_rows_are_rejected() {
+        assert_eq!(
+            Level::parse("###\n#.\n###"),
+            Err(LevelError::RaggedRow {
+                row: 1,
+                expected: 3,
+                found: 2
+            })
+        );
+    }
+
+    #[test]
+    fn unknown_tiles_are_rejected() {
+        assert_eq!(
+            Level::parse("#.#\n#x#"),
+            Err(LevelError::UnknownTile {
+                row: 1,
+                column: 1,
+                tile: 'x'
+            })
+        );
+    }
+
+    #[test]
+    fn multiple_starts_are_rejected() {
+        assert_eq!(
+            Level::parse("S.S"),
+            Err(LevelError::MultipleStarts {
+                first: Coordinate { x: 0, y: 0 },
+                second: Coordinate { x: 2, y: 0 }
+            })
+        );
+    }
+}
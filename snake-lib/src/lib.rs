@@ -1,50 +1,214 @@
+pub mod ai;
+pub mod clock;
+mod config;
+pub mod env;
+pub mod highscores;
+mod history;
+pub mod level;
+pub mod replay;
+mod rng;
+#[cfg(feature = "serde")]
+mod save;
+mod score;
+pub mod segment;
+pub mod sim;
+mod speed;
+pub mod theme;
+
 use std::collections::{HashSet, VecDeque};
+use std::fmt;
+use std::time::Duration;
+
+use history::Tick;
+
+pub use config::{ConfigError, GameConfig, Growth, SnakeStart, Walls, MAX_CELLS};
+pub use rng::Rng;
+pub use score::{Score, ScoreRules};
+pub use speed::{Difficulty, Ramp, Speed};
 
 #[derive(Hash, PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Coordinate {
     pub x: i32,
     pub y: i32,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Turn {
     Left,
     Right,
 }
 
-#[derive(Debug, PartialEq, Eq)]
-enum Direction {
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Direction {
     North,
     South,
     East,
     West,
 }
 
-type Snake = VecDeque<Coordinate>;
+/// A steering request: either relative to the way the snake is heading or an
+/// absolute compass direction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Input {
+    Turn(Turn),
+    Direction(Direction),
+}
+
+/// Index of a snake in `Game::snakes`.
+pub type SnakeId = usize;
 
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Snake {
+    pub id: SnakeId,
+    pub body: VecDeque<Coordinate>,
+    direction: Direction,
+    /// The way the snake last moved; `direction` is the way it will move next.
+    heading: Direction,
+    growth: u32,
+    score: Score,
+    pub alive: bool,
+}
+
+/// With the `serde` feature a game can be saved and loaded at any point.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Game {
-    pub snake: Snake,
+    pub snakes: Vec<Snake>,
+    pub food: HashSet<Coordinate>,
+    pub obstacles: HashSet<Coordinate>,
+    /// Rebuilt from the snakes' bodies when a game is loaded.
+    #[cfg_attr(feature = "serde", serde(skip))]
     active_cells: HashSet<Coordinate>,
-    direction: Direction,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    history: VecDeque<Tick>,
     pub width: u16,
     pub height: u16,
-    growth: u32,
+    pub ticks: u32,
+    pub config: GameConfig,
+    rng: Rng,
     pub game_over: bool,
+    /// Why the game ended; `None` while it is still going.
+    pub game_over_reason: Option<GameOverReason>,
+    /// Last snake standing in a multi-snake game; `None` for a draw or a
+    /// single-snake game.
+    pub winner: Option<SnakeId>,
 }
 
-#[derive(Debug)]
-pub struct SnakeChange {
-    pub removed: Option<Coordinate>,
-    pub added: Option<Coordinate>,
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GameOverReason {
+    /// Off the board, or into an obstacle, at this cell.
+    Wall(Coordinate),
+    /// Into its own body, counting segments from the head at 0.
+    SelfCollision { segment: usize },
+    /// Into another snake, alive or dead, or head to head with it.
+    OtherSnake(SnakeId),
+    /// Ended by whatever was running the game for going on too long.
+    Timeout,
+    /// The player gave up.
+    Quit,
+}
+
+/// Something that happened during a tick, in the order it happened.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GameEvent {
+    /// The head moved to `head`. `tail` is the cell the snake left, or `None`
+    /// while it is growing.
+    Moved {
+        id: SnakeId,
+        head: Coordinate,
+        tail: Option<Coordinate>,
+    },
+    /// The snake kept its tail this tick and is now `length` long.
+    Grew {
+        id: SnakeId,
+        length: usize,
+    },
+    /// The snake moved a different way from last tick.
+    Turned {
+        id: SnakeId,
+        from: Direction,
+        to: Direction,
+    },
+    /// `spawned` is where new food appeared, if there was room.
+    AteFood {
+        id: SnakeId,
+        food: Coordinate,
+        spawned: Option<Coordinate>,
+    },
+    Scored {
+        id: SnakeId,
+        points: u32,
+    },
+    /// Off the board or into an obstacle at `at`. Like `Moved`, `tail` is
+    /// the cell the snake left, if any; the rest of the body stays put.
+    HitWall {
+        id: SnakeId,
+        at: Coordinate,
+        tail: Option<Coordinate>,
+    },
+    HitSelf {
+        id: SnakeId,
+        at: Coordinate,
+        segment: usize,
+        tail: Option<Coordinate>,
+    },
+    HitSnake {
+        id: SnakeId,
+        other: SnakeId,
+        at: Coordinate,
+        tail: Option<Coordinate>,
+    },
+    LevelUp {
+        level: u32,
+    },
+    /// `reason` is the first crash of the final tick.
+    GameOver {
+        winner: Option<SnakeId>,
+        reason: GameOverReason,
+    },
+}
+
+impl fmt::Display for GameOverReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameOverReason::Wall(Coordinate { x, y }) => write!(f, "hit a wall at ({}, {})", x, y),
+            GameOverReason::SelfCollision { segment } => {
+                write!(f, "bit itself at segment {}", segment)
+            }
+            GameOverReason::OtherSnake(id) => write!(f, "ran into player {}", id + 1),
+            GameOverReason::Timeout => write!(f, "ran out of time"),
+            GameOverReason::Quit => write!(f, "quit"),
+        }
+    }
 }
 
 impl Coordinate {
     fn out_of_bounds(&self, game: &Game) -> bool {
         self.x < 0 || self.x >= game.width.into() || self.y < 0 || self.y >= game.height.into()
     }
+
+    fn wrap(&self, game: &Game) -> Coordinate {
+        Coordinate {
+            x: self.x.rem_euclid(game.width.into()),
+            y: self.y.rem_euclid(game.height.into()),
+        }
+    }
 }
 
 impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::North,
+        Direction::South,
+        Direction::East,
+        Direction::West,
+    ];
+
     fn turn(&self, turn: &Turn) -> Direction {
         match (self, turn) {
             (Direction::East, Turn::Left) => Direction::North,
@@ -57,6 +221,15 @@ impl Direction {
             (Direction::South, Turn::Right) => Direction::West,
         }
     }
+
+    pub fn opposite(&self) -> Direction {
+        match self {
+            Direction::North => Direction::South,
+            Direction::South => Direction::North,
+            Direction::East => Direction::West,
+            Direction::West => Direction::East,
+        }
+    }
 }
 
 impl Coordinate {
@@ -82,87 +255,397 @@ impl Coordinate {
     }
 }
 
+impl Snake {
+    /// Only empty for a single-segment snake that died as its tail moved.
+    pub fn head(&self) -> Option<&Coordinate> {
+        self.body.front()
+    }
+
+    pub fn len(&self) -> usize {
+        self.body.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.body.is_empty()
+    }
+
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    pub fn score(&self) -> Score {
+        self.score
+    }
+}
+
 impl Game {
-    pub fn new(width: &u16, height: &u16) -> Game {
-        Game {
-            snake: VecDeque::from([Coordinate {
-                x: (width / 2).into(),
-                y: (height / 2).into(),
-            }]),
-            active_cells: HashSet::from([Coordinate {
-                x: (width / 2).into(),
-                y: (height / 2).into(),
-            }]),
-            direction: Direction::East,
-            width: *width,
-            height: *height,
-            growth: 3,
+    pub fn new(config: &GameConfig) -> Result<Game, ConfigError> {
+        config.validate()?;
+        let snakes: Vec<Snake> = config
+            .snakes
+            .iter()
+            .enumerate()
+            .map(|(id, start)| Snake {
+                id,
+                body: VecDeque::from([start.position.clone()]),
+                direction: start.direction,
+                heading: start.direction,
+                growth: config.length - 1,
+                score: Score::default(),
+                alive: true,
+            })
+            .collect();
+        let mut game = Game {
+            active_cells: snakes.iter().flat_map(|s| s.body.clone()).collect(),
+            snakes,
+            history: VecDeque::new(),
+            food: HashSet::new(),
+            obstacles: config.obstacles.clone(),
+            width: config.width,
+            height: config.height,
+            ticks: 0,
+            config: config.clone(),
+            rng: Rng::new(config.seed),
             game_over: false,
+            game_over_reason: None,
+            winner: None,
+        };
+        for _ in 0..config.food {
+            game.spawn_food();
+        }
+        Ok(game)
+    }
+
+    pub fn turn(&mut self, id: SnakeId, turn: &Turn) {
+        let snake = &mut self.snakes[id];
+        snake.direction = snake.direction.turn(turn);
+    }
+
+    /// Applies an input, returning `false` if it was ignored because it
+    /// would reverse the snake back into itself. Reversal is judged against
+    /// the way the snake last moved, so two inputs in one tick can't double
+    /// back into the neck.
+    pub fn steer(&mut self, id: SnakeId, input: &Input) -> bool {
+        let snake = &mut self.snakes[id];
+        let direction = match input {
+            Input::Turn(t) => snake.direction.turn(t),
+            Input::Direction(d) => *d,
+        };
+        if direction == snake.heading.opposite() {
+            return false;
         }
+        snake.direction = direction;
+        true
+    }
+
+    pub fn grow(&mut self, id: SnakeId, n: &u32) {
+        // Growth comes from configs that may have been loaded from a file.
+        let snake = &mut self.snakes[id];
+        snake.growth = snake.growth.saturating_add(*n);
     }
 
-    pub fn turn(&mut self, turn: &Turn) {
-        self.direction = self.direction.turn(turn);
+    pub fn score(&self, id: SnakeId) -> Score {
+        self.snakes[id].score
     }
 
-    pub fn grow(&mut self, n: &u32) {
-        self.growth += n;
+    /// The speed level, set by whichever snake is furthest along.
+    pub fn level(&self) -> u32 {
+        let start = usize::try_from(self.config.length).unwrap_or(usize::MAX);
+        self.snakes
+            .iter()
+            .map(|s| {
+                let grown = u32::try_from(s.len().saturating_sub(start)).unwrap_or(u32::MAX);
+                self.config.speed.level(s.score.total(), grown)
+            })
+            .max()
+            .unwrap_or(0)
     }
 
-    pub fn advance(&mut self) -> SnakeChange {
-        let mut removed = None;
-        let mut added = None;
+    /// How long front ends should wait between ticks at the current level.
+    pub fn tick_interval(&self) -> Duration {
+        self.config.speed.interval(self.level())
+    }
+
+    /// Cells that are not occupied by a snake, a wall or food.
+    pub fn free_cells(&self) -> Vec<Coordinate> {
+        (0..i32::from(self.height))
+            .flat_map(|y| (0..i32::from(self.width)).map(move |x| Coordinate { x, y }))
+            .filter(|c| {
+                !self.active_cells.contains(c)
+                    && !self.obstacles.contains(c)
+                    && !self.food.contains(c)
+            })
+            .collect()
+    }
+
+    /// Places a new piece of food on a free cell, returning where it went or
+    /// `None` if the board is full.
+    pub fn spawn_food(&mut self) -> Option<Coordinate> {
+        let free = self.free_cells();
+        if free.is_empty() {
+            return None;
+        }
+        let c = free[self.rng.below(free.len())].clone();
+        self.food.insert(c.clone());
+        Some(c)
+    }
+
+    /// Stops the game for a reason other than a crash, such as a timeout or
+    /// the player quitting. Does nothing if it is already over.
+    pub fn end(&mut self, reason: GameOverReason) {
         if !self.game_over {
-            let new_front = self.snake.front().unwrap().advance(&self.direction);
+            self.game_over = true;
+            self.game_over_reason = Some(reason);
+        }
+    }
+
+    /// Moves every live snake one cell. All snakes move at once: a head may
+    /// follow a tail that moves away this tick, but two heads meeting (or
+    /// passing through each other) eliminates both snakes.
+    pub fn advance(&mut self) -> Vec<GameEvent> {
+        if self.game_over {
+            return Vec::new();
+        }
 
-            if self.growth > 0 {
-                self.growth -= 1;
+        let mut tick = Tick::start(self);
+        let level = self.level();
+        self.ticks += 1;
+        if let Growth::Timed { every, by } = self.config.growth {
+            if self.ticks.is_multiple_of(every) {
+                for snake in self.snakes.iter_mut().filter(|s| s.alive) {
+                    snake.growth = snake.growth.saturating_add(by);
+                }
+            }
+        }
+
+        let mut events: Vec<GameEvent> = Vec::new();
+        let mut moves: Vec<Move> = Vec::new();
+        for id in 0..self.snakes.len() {
+            if !self.snakes[id].alive {
+                continue;
+            }
+            let old_front = self.snakes[id].head().unwrap().clone();
+            let mut new_front = old_front.advance(&self.snakes[id].direction);
+            if self.config.walls == Walls::Wrap {
+                new_front = new_front.wrap(self);
+            }
+
+            let snake = &mut self.snakes[id];
+            if snake.direction != snake.heading {
+                events.push(GameEvent::Turned {
+                    id,
+                    from: snake.heading,
+                    to: snake.direction,
+                });
+                snake.heading = snake.direction;
+            }
+            let mut removed = None;
+            if snake.growth > 0 {
+                snake.growth -= 1;
             } else {
-                let r = self.snake.pop_back().unwrap();
+                let r = snake.body.pop_back().unwrap();
                 self.active_cells.remove(&r);
                 removed = Some(r);
             }
 
-            if new_front.out_of_bounds(self) || self.active_cells.contains(&new_front) {
-                self.game_over = true;
-            } else {
-                self.active_cells.insert(new_front.clone());
-                self.snake.push_front(new_front.clone());
-                added = Some(new_front);
+            moves.push(Move {
+                id,
+                old_front,
+                new_front,
+                removed,
+            });
+        }
+
+        let crashes: Vec<Option<GameOverReason>> =
+            moves.iter().map(|m| self.collision(m, &moves)).collect();
+
+        let tails = moves.iter().map(|m| (m.id, m.removed.clone())).collect();
+        let mut cause = None;
+        // Where each `AteFood` event is, to fill in its replacement once
+        // every snake has moved.
+        let mut meals = Vec::new();
+        for (m, crash) in moves.into_iter().zip(crashes) {
+            let Move {
+                id,
+                new_front,
+                removed,
+                ..
+            } = m;
+            if let Some(crash) = crash {
+                self.snakes[id].alive = false;
+                events.push(match &crash {
+                    GameOverReason::Wall(at) => GameEvent::HitWall {
+                        id,
+                        at: at.clone(),
+                        tail: removed,
+                    },
+                    GameOverReason::SelfCollision { segment } => GameEvent::HitSelf {
+                        id,
+                        at: new_front,
+                        segment: *segment,
+                        tail: removed,
+                    },
+                    GameOverReason::OtherSnake(other) => GameEvent::HitSnake {
+                        id,
+                        other: *other,
+                        at: new_front,
+                        tail: removed,
+                    },
+                    GameOverReason::Timeout | GameOverReason::Quit => unreachable!(),
+                });
+                cause.get_or_insert(crash);
+                continue;
             }
+            self.active_cells.insert(new_front.clone());
+            let snake = &mut self.snakes[id];
+            snake.body.push_front(new_front.clone());
+            let grew = removed.is_none();
+            events.push(GameEvent::Moved {
+                id,
+                head: new_front.clone(),
+                tail: removed,
+            });
+            if grew {
+                events.push(GameEvent::Grew {
+                    id,
+                    length: snake.body.len(),
+                });
+            }
+            let mut points = self.config.scoring.survive(&mut snake.score, self.ticks);
+            if self.food.remove(&new_front) {
+                points = points
+                    .saturating_add(self.config.scoring.eat(&mut snake.score, snake.body.len()));
+                if let Growth::PerFood(n) = self.config.growth {
+                    self.grow(id, &n);
+                }
+                meals.push(events.len());
+                events.push(GameEvent::AteFood {
+                    id,
+                    food: new_front,
+                    spawned: None,
+                });
+            }
+            if points > 0 {
+                events.push(GameEvent::Scored { id, points });
+            }
+        }
+
+        // Replacement food goes down against where the snakes ended up, so
+        // it can't land in front of a snake that has yet to move.
+        for i in meals {
+            if let GameEvent::AteFood { spawned, .. } = &mut events[i] {
+                *spawned = self.spawn_food();
+            }
+        }
+
+        if self.level() > level {
+            events.push(GameEvent::LevelUp {
+                level: self.level(),
+            });
+        }
+
+        let alive: Vec<SnakeId> = self
+            .snakes
+            .iter()
+            .filter(|s| s.alive)
+            .map(|s| s.id)
+            .collect();
+        if self.snakes.len() == 1 {
+            self.game_over = alive.is_empty();
+        } else if alive.len() <= 1 {
+            self.game_over = true;
+            self.winner = alive.first().copied();
+        }
+        if let (true, Some(reason)) = (self.game_over, cause) {
+            self.game_over_reason = Some(reason.clone());
+            events.push(GameEvent::GameOver {
+                winner: self.winner,
+                reason,
+            });
         }
-        SnakeChange { removed, added }
+
+        tick.finish(tails, &events);
+        self.remember(tick);
+        events
+    }
+
+    /// What, if anything, a snake crashes into while the others make their
+    /// `moves`.
+    fn collision(&self, m: &Move, moves: &[Move]) -> Option<GameOverReason> {
+        if m.new_front.out_of_bounds(self) || self.obstacles.contains(&m.new_front) {
+            return Some(GameOverReason::Wall(m.new_front.clone()));
+        }
+        if let Some(other) = moves.iter().find(|o| {
+            o.id != m.id
+                && (o.new_front == m.new_front
+                    || (o.new_front == m.old_front && m.new_front == o.old_front))
+        }) {
+            return Some(GameOverReason::OtherSnake(other.id));
+        }
+        if !self.active_cells.contains(&m.new_front) {
+            return None;
+        }
+        if let Some(segment) = self.snakes[m.id]
+            .body
+            .iter()
+            .position(|c| *c == m.new_front)
+        {
+            return Some(GameOverReason::SelfCollision { segment });
+        }
+        self.snakes
+            .iter()
+            .find(|s| s.body.contains(&m.new_front))
+            .map(|s| GameOverReason::OtherSnake(s.id))
     }
 }
 
+/// One snake's step during `Game::advance`, before collisions are checked.
+struct Move {
+    id: SnakeId,
+    old_front: Coordinate,
+    new_front: Coordinate,
+    removed: Option<Coordinate>,
+}
+
 pub trait GameDisplay {
     fn initialize(&self, game: &Game);
     fn game_over(&self, game: &Game);
-    fn update(&self, game: &Game, change: &SnakeChange);
+    fn update(&self, game: &Game, events: &[GameEvent]);
+}
+
+/// For tests that only care about the game.
+#[cfg(test)]
+pub(crate) struct NoDisplay;
+
+#[cfg(test)]
+impl GameDisplay for NoDisplay {
+    fn initialize(&self, _game: &Game) {}
+    fn game_over(&self, _game: &Game) {}
+    fn update(&self, _game: &Game, _events: &[GameEvent]) {}
 }
+
 pub trait GameInput {
-    fn poll(&self) -> Option<Turn>;
+    /// Called once per tick for each live snake.
+    fn poll(&self, id: SnakeId) -> Option<Input>;
 }
 
-pub fn game_step<D: GameDisplay, I: GameInput>(
-    counter: &mut u32,
-    game: &mut Game,
-    display: &D,
-    input: &I,
-) {
-    if let Some(t) = input.poll() {
-        game.turn(&t);
+impl<I: GameInput + ?Sized> GameInput for &I {
+    fn poll(&self, id: SnakeId) -> Option<Input> {
+        (**self).poll(id)
     }
+}
 
-    if *counter > 20u32 {
-        *counter = 0u32;
-        game.grow(&3);
-    } else {
-        *counter += 1;
+pub fn game_step<D: GameDisplay, I: GameInput>(game: &mut Game, display: &D, input: &I) {
+    for id in 0..game.snakes.len() {
+        if game.snakes[id].alive {
+            if let Some(i) = input.poll(id) {
+                game.steer(id, &i);
+            }
+        }
     }
 
-    let change = game.advance();
-    display.update(game, &change);
+    let events = game.advance();
+    display.update(game, &events);
     if game.game_over {
         display.game_over(game);
     }
@@ -171,10 +654,15 @@ pub fn game_step<D: GameDisplay, I: GameInput>(
 #[cfg(test)]
 mod test {
     use std::cell::RefCell;
+    use std::collections::HashSet;
 
+    use crate::level::Level;
     use crate::{
-        game_step, Coordinate, Direction, Game, GameDisplay, GameInput, SnakeChange, Turn,
+        game_step, ConfigError, Coordinate, Direction, Game, GameConfig, GameDisplay, GameEvent,
+        GameInput, GameOverReason, Growth, Input, Ramp, Score, ScoreRules, SnakeId, Speed, Turn,
+        Walls,
     };
+    use std::time::Duration;
     use test_case::test_case;
     #[test_case(Direction::North)]
     #[test_case(Direction::South)]
@@ -233,45 +721,127 @@ mod test {
     #[test_case(99, 99, 49, 49)]
     #[test_case(100, 200, 50, 100)]
     fn snake_starts_in_centre_of_board(width: u16, height: u16, x: i32, y: i32) {
-        let game = Game::new(&width, &height);
-        assert_eq!(game.snake.len(), 1);
-        assert_eq!(game.snake.front().unwrap(), &Coordinate { x, y });
+        let game = Game::new(&GameConfig::new(width, height)).unwrap();
+        assert_eq!(game.snakes[0].body.len(), 1);
+        assert_eq!(game.snakes[0].body.front().unwrap(), &Coordinate { x, y });
+    }
+
+    #[test]
+    fn game_rejects_empty_board() {
+        assert_eq!(
+            Game::new(&GameConfig::new(0, 0)).err(),
+            Some(ConfigError::EmptyBoard {
+                width: 0,
+                height: 0
+            })
+        );
+    }
+
+    #[test]
+    fn snake_starts_where_configured() {
+        let mut game = Game::new(
+            &GameConfig::new(20, 20)
+                .start(Coordinate { x: 3, y: 4 })
+                .direction(Direction::South),
+        )
+        .unwrap();
+        assert_eq!(
+            game.snakes[0].body.front().unwrap(),
+            &Coordinate { x: 3, y: 4 }
+        );
+        game.advance();
+        assert_eq!(
+            game.snakes[0].body.front().unwrap(),
+            &Coordinate { x: 3, y: 5 }
+        );
+    }
+
+    #[test_case(1)]
+    #[test_case(2)]
+    #[test_case(7)]
+    fn snake_grows_to_configured_length(length: u32) {
+        let mut game = Game::new(&GameConfig::new(20, 20).length(length).food(0)).unwrap();
+        for _ in 0..8 {
+            game.advance();
+        }
+        assert_eq!(game.snakes[0].body.len(), length as usize);
+    }
+
+    #[test]
+    fn timed_growth_grows_without_food() {
+        let mut game = Game::new(
+            &GameConfig::new(40, 40)
+                .length(1)
+                .food(0)
+                .growth(Growth::Timed { every: 5, by: 2 }),
+        )
+        .unwrap();
+        for _ in 0..4 {
+            game.advance();
+        }
+        assert_eq!(game.snakes[0].body.len(), 1);
+        game.advance();
+        game.advance();
+        assert_eq!(game.snakes[0].body.len(), 3);
+        for _ in 0..3 {
+            game.advance();
+            assert_eq!(game.snakes[0].body.len(), 3);
+        }
+        game.advance();
+        game.advance();
+        assert_eq!(game.snakes[0].body.len(), 5);
+    }
+
+    #[test_case(Growth::PerFood(u32::MAX) ; "per food")]
+    #[test_case(Growth::Timed { every: 1, by: u32::MAX } ; "timed")]
+    fn huge_growth_saturates(growth: Growth) {
+        let mut game = Game::new(&GameConfig::new(20, 20).growth(growth)).unwrap();
+        game.food = HashSet::from([Coordinate { x: 11, y: 10 }]);
+        game.grow(0, &u32::MAX);
+        game.advance();
+        assert!(game.snakes[0].growth >= u32::MAX - 1);
+    }
+
+    #[test]
+    fn configured_number_of_food_is_placed() {
+        let game = Game::new(&GameConfig::new(20, 20).food(5)).unwrap();
+        assert_eq!(game.food.len(), 5);
     }
 
     #[test]
     fn snake_initially_grows_to_length_four() {
-        let mut game = Game::new(&100, &100);
-        assert_eq!(game.snake.len(), 1);
+        let mut game = Game::new(&GameConfig::new(100, 100)).unwrap();
+        assert_eq!(game.snakes[0].body.len(), 1);
         game.advance();
-        assert_eq!(game.snake.len(), 2);
+        assert_eq!(game.snakes[0].body.len(), 2);
         game.advance();
-        assert_eq!(game.snake.len(), 3);
+        assert_eq!(game.snakes[0].body.len(), 3);
         game.advance();
-        assert_eq!(game.snake.len(), 4);
+        assert_eq!(game.snakes[0].body.len(), 4);
         for _ in 0..5 {
             game.advance();
-            assert_eq!(game.snake.len(), 4);
+            assert_eq!(game.snakes[0].body.len(), 4);
         }
     }
 
     #[test]
     fn snake_can_grow_to_any_length() {
-        let mut game = Game::new(&100, &100);
-        game.grow(&6);
+        let mut game = Game::new(&GameConfig::new(100, 100)).unwrap();
+        game.grow(0, &6);
         for i in 1..=10 {
-            assert_eq!(game.snake.len(), i);
+            assert_eq!(game.snakes[0].body.len(), i);
             game.advance();
         }
 
         for _ in 0..5 {
             game.advance();
-            assert_eq!(game.snake.len(), 10);
+            assert_eq!(game.snakes[0].body.len(), 10);
         }
     }
 
     #[test]
     fn snake_hits_wall_game_over() {
-        let mut game = Game::new(&20, &20);
+        let mut game = Game::new(&GameConfig::new(20, 20)).unwrap();
         assert!(!game.game_over);
         for _ in 0..9 {
             game.advance();
@@ -279,51 +849,379 @@ mod test {
         }
         game.advance();
         assert!(game.game_over);
+        assert_eq!(
+            game.game_over_reason,
+            Some(GameOverReason::Wall(Coordinate { x: 20, y: 10 }))
+        );
+    }
+
+    #[test]
+    fn wall_crash_is_reported() {
+        let mut game = Game::new(&GameConfig::new(20, 20)).unwrap();
+        for _ in 0..9 {
+            game.advance();
+        }
+        let wall = Coordinate { x: 20, y: 10 };
+        assert_eq!(
+            game.advance(),
+            vec![
+                GameEvent::HitWall {
+                    id: 0,
+                    at: wall.clone(),
+                    tail: Some(Coordinate { x: 16, y: 10 }),
+                },
+                GameEvent::GameOver {
+                    winner: None,
+                    reason: GameOverReason::Wall(wall),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn crash_into_another_snake_is_reported() {
+        let mut game = two_snakes((5, 5, Direction::East), (6, 3, Direction::South));
+        game.advance();
+        let events = game.advance();
+        assert!(events.contains(&GameEvent::HitSnake {
+            id: 1,
+            other: 0,
+            at: Coordinate { x: 6, y: 5 },
+            tail: None,
+        }));
+        assert_eq!(
+            events.last(),
+            Some(&GameEvent::GameOver {
+                winner: Some(0),
+                reason: GameOverReason::OtherSnake(0),
+            })
+        );
+    }
+
+    #[test_case(Direction::North, 11, Coordinate { x: 10, y: 19 })]
+    #[test_case(Direction::South, 10, Coordinate { x: 10, y: 0 })]
+    #[test_case(Direction::East, 10, Coordinate { x: 0, y: 10 })]
+    #[test_case(Direction::West, 11, Coordinate { x: 19, y: 10 })]
+    fn snake_wraps_around_wall(direction: Direction, steps: usize, wrapped: Coordinate) {
+        let mut game = Game::new(
+            &GameConfig::new(20, 20)
+                .walls(Walls::Wrap)
+                .direction(direction)
+                .food(0),
+        )
+        .unwrap();
+        let mut last = None;
+        for _ in 0..steps {
+            last = heads(&game.advance()).pop();
+            assert!(!game.game_over);
+        }
+        assert_eq!(last, Some(wrapped.clone()));
+        assert_eq!(game.snakes[0].body.front(), Some(&wrapped));
+    }
+
+    #[test]
+    fn wrapping_snake_keeps_moving_across_board() {
+        let mut game = Game::new(&GameConfig::new(20, 20).walls(Walls::Wrap).food(0)).unwrap();
+        for _ in 0..20 {
+            game.advance();
+        }
+        assert!(!game.game_over);
+        assert_eq!(
+            game.snakes[0].body.front(),
+            Some(&Coordinate { x: 10, y: 10 })
+        );
+        assert_eq!(game.snakes[0].body.len(), 4);
+    }
+
+    #[test]
+    fn wrapping_snake_still_bites_self() {
+        let mut game =
+            Game::new(&GameConfig::new(5, 1).walls(Walls::Wrap).length(6).food(0)).unwrap();
+        for _ in 0..4 {
+            game.advance();
+            assert!(!game.game_over);
+        }
+        game.advance();
+        assert!(game.game_over);
+    }
+
+    #[test]
+    fn snake_hits_obstacle_game_over() {
+        let level = Level::parse("......\n.S..#.\n......").unwrap();
+        let mut game = Game::new(&GameConfig::from_level(&level).food(0)).unwrap();
+        game.advance();
+        game.advance();
+        assert!(!game.game_over);
+        game.advance();
+        assert!(game.game_over);
+        assert_eq!(
+            game.game_over_reason,
+            Some(GameOverReason::Wall(Coordinate { x: 4, y: 1 }))
+        );
+        assert_eq!(
+            game.snakes[0].body.front(),
+            Some(&Coordinate { x: 3, y: 1 })
+        );
+    }
+
+    #[test]
+    fn food_never_spawns_on_obstacles() {
+        let level = Level::parse("#####\n#S..#\n#####").unwrap();
+        let mut game = Game::new(&GameConfig::from_level(&level).food(0)).unwrap();
+        assert_eq!(
+            game.free_cells(),
+            vec![Coordinate { x: 2, y: 1 }, Coordinate { x: 3, y: 1 }]
+        );
+        for _ in 0..2 {
+            let c = game.spawn_food().unwrap();
+            assert!(!game.obstacles.contains(&c));
+        }
+        assert_eq!(game.spawn_food(), None);
     }
 
     #[test]
     fn snake_bites_self_game_over() {
-        let mut game = Game::new(&20, &20);
-        game.grow(&10);
+        let mut game = Game::new(&GameConfig::new(20, 20)).unwrap();
+        game.grow(0, &10);
         assert!(!game.game_over);
         for _ in 0..3 {
             game.advance();
             assert!(!game.game_over);
-            game.turn(&Turn::Left);
+            game.turn(0, &Turn::Left);
         }
-        game.turn(&Turn::Left);
+        game.turn(0, &Turn::Left);
         game.advance();
         assert!(game.game_over);
+        assert_eq!(
+            game.game_over_reason,
+            Some(GameOverReason::SelfCollision { segment: 1 })
+        );
+    }
+
+    #[test]
+    fn ending_a_game_keeps_the_first_reason() {
+        let mut game = Game::new(&GameConfig::new(20, 20)).unwrap();
+        game.end(GameOverReason::Quit);
+        game.end(GameOverReason::Timeout);
+        assert!(game.game_over);
+        assert_eq!(game.game_over_reason, Some(GameOverReason::Quit));
+        assert!(game.advance().is_empty());
     }
 
     #[test]
     fn advance_returns_changed_coordinates() {
-        let mut game = Game::new(&20, &20);
+        let mut game = Game::new(&GameConfig::new(20, 20)).unwrap();
+
+        assert_eq!(
+            game.advance(),
+            vec![
+                GameEvent::Moved {
+                    id: 0,
+                    head: Coordinate { x: 11, y: 10 },
+                    tail: None,
+                },
+                GameEvent::Grew { id: 0, length: 2 },
+            ]
+        );
+
+        assert_eq!(
+            game.advance(),
+            vec![
+                GameEvent::Moved {
+                    id: 0,
+                    head: Coordinate { x: 12, y: 10 },
+                    tail: None,
+                },
+                GameEvent::Grew { id: 0, length: 3 },
+            ]
+        );
+
+        assert_eq!(
+            game.advance(),
+            vec![
+                GameEvent::Moved {
+                    id: 0,
+                    head: Coordinate { x: 13, y: 10 },
+                    tail: None,
+                },
+                GameEvent::Grew { id: 0, length: 4 },
+            ]
+        );
+
+        assert_eq!(
+            game.advance(),
+            vec![GameEvent::Moved {
+                id: 0,
+                head: Coordinate { x: 14, y: 10 },
+                tail: Some(Coordinate { x: 10, y: 10 }),
+            }]
+        );
+
+        game.turn(0, &Turn::Right);
+        assert_eq!(
+            game.advance(),
+            vec![
+                GameEvent::Turned {
+                    id: 0,
+                    from: Direction::East,
+                    to: Direction::South,
+                },
+                GameEvent::Moved {
+                    id: 0,
+                    head: Coordinate { x: 14, y: 11 },
+                    tail: Some(Coordinate { x: 11, y: 10 }),
+                },
+            ]
+        );
 
-        let SnakeChange { added, removed } = game.advance();
-        assert_eq!(added, Some(Coordinate { x: 11, y: 10 }));
-        assert!(removed.is_none());
+        assert_eq!(
+            game.advance(),
+            vec![GameEvent::Moved {
+                id: 0,
+                head: Coordinate { x: 14, y: 12 },
+                tail: Some(Coordinate { x: 12, y: 10 }),
+            }]
+        );
+    }
 
-        let SnakeChange { added, removed } = game.advance();
-        assert_eq!(added, Some(Coordinate { x: 12, y: 10 }));
-        assert!(removed.is_none());
+    /// Where each snake that moved ended up.
+    fn heads(events: &[GameEvent]) -> Vec<Coordinate> {
+        events
+            .iter()
+            .filter_map(|e| match e {
+                GameEvent::Moved { head, .. } => Some(head.clone()),
+                _ => None,
+            })
+            .collect()
+    }
 
-        let SnakeChange { added, removed } = game.advance();
-        assert_eq!(added, Some(Coordinate { x: 13, y: 10 }));
-        assert!(removed.is_none());
+    fn points(events: &[GameEvent]) -> u32 {
+        events
+            .iter()
+            .map(|e| match e {
+                GameEvent::Scored { points, .. } => *points,
+                _ => 0,
+            })
+            .sum()
+    }
 
-        let SnakeChange { added, removed } = game.advance();
-        assert_eq!(added, Some(Coordinate { x: 14, y: 10 }));
-        assert_eq!(removed, Some(Coordinate { x: 10, y: 10 }));
+    fn two_snakes(a: (i32, i32, Direction), b: (i32, i32, Direction)) -> Game {
+        Game::new(
+            &GameConfig::new(20, 20)
+                .start(Coordinate { x: a.0, y: a.1 })
+                .direction(a.2)
+                .add_snake(Coordinate { x: b.0, y: b.1 }, b.2)
+                .length(3)
+                .food(0),
+        )
+        .unwrap()
+    }
 
-        game.turn(&Turn::Right);
-        let SnakeChange { added, removed } = game.advance();
-        assert_eq!(added, Some(Coordinate { x: 14, y: 11 }));
-        assert_eq!(removed, Some(Coordinate { x: 11, y: 10 }));
+    #[test]
+    fn snakes_move_and_steer_independently() {
+        let mut game = two_snakes((2, 2, Direction::East), (17, 17, Direction::West));
+        game.steer(1, &Input::Direction(Direction::North));
+        assert_eq!(
+            heads(&game.advance()),
+            vec![Coordinate { x: 3, y: 2 }, Coordinate { x: 17, y: 16 }]
+        );
+        assert!(!game.game_over);
+    }
 
-        let SnakeChange { added, removed } = game.advance();
-        assert_eq!(added, Some(Coordinate { x: 14, y: 12 }));
-        assert_eq!(removed, Some(Coordinate { x: 12, y: 10 }));
+    #[test]
+    fn food_respawns_after_every_snake_has_moved() {
+        // With this seed the replacement used to land on (7, 5), where the
+        // second snake was about to move, and be eaten the same tick.
+        let mut game = Game::new(
+            &GameConfig::new(9, 9)
+                .seed(32)
+                .start(Coordinate { x: 4, y: 5 })
+                .direction(Direction::East)
+                .add_snake(Coordinate { x: 8, y: 5 }, Direction::West),
+        )
+        .unwrap();
+        game.food = HashSet::from([Coordinate { x: 5, y: 5 }]);
+        let events = game.advance();
+        let eaten: Vec<SnakeId> = events
+            .iter()
+            .filter_map(|e| match e {
+                GameEvent::AteFood { id, .. } => Some(*id),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(eaten, vec![0]);
+        assert_eq!(game.food.len(), 1);
+        for snake in &game.snakes {
+            assert!(game.food.iter().all(|c| !snake.body.contains(c)));
+        }
+    }
+
+    #[test]
+    fn head_on_collision_eliminates_both() {
+        let mut game = two_snakes((5, 5, Direction::East), (9, 5, Direction::West));
+        game.advance();
+        assert!(!game.game_over);
+        game.advance();
+        assert!(!game.snakes[0].alive);
+        assert!(!game.snakes[1].alive);
+        assert!(game.game_over);
+        assert_eq!(game.winner, None);
+        assert_eq!(game.game_over_reason, Some(GameOverReason::OtherSnake(1)));
+    }
+
+    #[test]
+    fn snakes_passing_through_each_other_eliminates_both() {
+        let mut game = two_snakes((5, 5, Direction::East), (6, 5, Direction::West));
+        game.advance();
+        assert!(!game.snakes[0].alive);
+        assert!(!game.snakes[1].alive);
+        assert_eq!(game.winner, None);
+    }
+
+    #[test]
+    fn running_into_another_snake_eliminates_only_the_attacker() {
+        let mut game = two_snakes((5, 5, Direction::East), (6, 3, Direction::South));
+        game.advance();
+        assert!(game.snakes[0].alive);
+        assert!(game.snakes[1].alive);
+        game.advance();
+        assert!(game.snakes[0].alive);
+        assert!(!game.snakes[1].alive);
+        assert!(game.game_over);
+        assert_eq!(game.winner, Some(0));
+    }
+
+    #[test]
+    fn last_snake_standing_wins() {
+        let mut game = Game::new(&GameConfig::versus(20, 20, 3).food(0)).unwrap();
+        game.steer(0, &Input::Direction(Direction::North));
+        game.steer(1, &Input::Direction(Direction::North));
+        for _ in 0..game.snakes[0].head().unwrap().y {
+            game.advance();
+        }
+        assert!(game.snakes[0].alive);
+        game.advance();
+        assert!(!game.snakes[0].alive);
+        assert!(!game.game_over);
+        assert!(game.advance().iter().all(|e| !matches!(
+            e,
+            GameEvent::Moved { id: 0, .. } | GameEvent::HitWall { id: 0, .. }
+        )));
+        while !game.game_over {
+            game.advance();
+        }
+        assert!(!game.snakes[1].alive);
+        assert!(game.snakes[2].alive);
+        assert_eq!(game.winner, Some(2));
+    }
+
+    #[test]
+    fn single_snake_has_no_winner() {
+        let mut game = Game::new(&GameConfig::new(5, 5)).unwrap();
+        while !game.game_over {
+            game.advance();
+        }
+        assert_eq!(game.winner, None);
     }
 
     struct MockDisplay {
@@ -345,10 +1243,10 @@ mod test {
         fn initialize(&self, _game: &Game) {
             self.calls.borrow_mut().push("initialize".to_string());
         }
-        fn update(&self, _game: &Game, change: &SnakeChange) {
+        fn update(&self, _game: &Game, events: &[GameEvent]) {
             self.calls
                 .borrow_mut()
-                .push(format!("update({:?})", change));
+                .push(format!("update({:?})", events));
         }
     }
 
@@ -357,12 +1255,12 @@ mod test {
     }
 
     struct MockInputImpl {
-        inputs: Vec<Option<Turn>>,
+        inputs: Vec<Option<Input>>,
         i: usize,
     }
 
     impl MockInput {
-        fn new(inputs: Vec<Option<Turn>>) -> MockInput {
+        fn new(inputs: Vec<Option<Input>>) -> MockInput {
             MockInput {
                 imp: RefCell::new(MockInputImpl { inputs, i: 0 }),
             }
@@ -378,126 +1276,337 @@ mod test {
     }
 
     impl MockInputImpl {
-        fn poll(&mut self) -> Option<Turn> {
+        fn poll(&mut self) -> Option<Input> {
             if self.i < self.inputs.len() {
                 let r = self.inputs.get(self.i).unwrap();
                 self.i += 1;
-                return r.clone();
+                r.clone()
             } else {
-                return None;
+                None
             }
         }
     }
 
     impl GameInput for MockInput {
-        fn poll(&self) -> Option<Turn> {
+        fn poll(&self, _id: SnakeId) -> Option<Input> {
             self.imp.borrow_mut().poll()
         }
     }
 
     #[test]
-    fn counter_increases_each_step() {
-        let mut game = Game::new(&20, &20);
-        let mut counter = 0;
-        let mut prev_counter = 0;
-        for _ in 0..10 {
-            game_step(
-                &mut counter,
-                &mut game,
-                &MockDisplay::new(),
-                &MockInput::no_input(),
-            );
-            assert_eq!(counter, prev_counter + 1);
-            prev_counter = counter;
+    fn game_starts_with_food_on_a_free_cell() {
+        let game = Game::new(&GameConfig::new(20, 20)).unwrap();
+        assert_eq!(game.food.len(), 1);
+        for c in &game.food {
+            assert!(!c.out_of_bounds(&game));
+            assert!(!game.snakes[0].body.contains(c));
+        }
+    }
+
+    #[test]
+    fn same_seed_places_food_identically() {
+        let config = GameConfig::new(20, 20).seed(1234);
+        let mut a = Game::new(&config).unwrap();
+        let mut b = Game::new(&config).unwrap();
+        assert_eq!(a.food, b.food);
+        for _ in 0..5 {
+            assert_eq!(a.spawn_food(), b.spawn_food());
         }
+        assert_eq!(a.food, b.food);
     }
+
     #[test]
-    fn snake_grows_by_three_when_counter_is_more_than_twenty() {
-        let mut game = Game::new(&20, &20);
-        assert_eq!(game.growth, 3);
-        let mut counter = 21;
-        game_step(
-            &mut counter,
-            &mut game,
-            &MockDisplay::new(),
-            &MockInput::no_input(),
+    fn different_seeds_place_food_differently() {
+        let foods: HashSet<Vec<Coordinate>> = (0..10)
+            .map(|seed| {
+                let game = Game::new(&GameConfig::new(20, 20).seed(seed)).unwrap();
+                game.food.into_iter().collect()
+            })
+            .collect();
+        assert!(foods.len() > 1);
+    }
+
+    #[test]
+    fn snake_does_not_grow_without_food() {
+        let mut game = Game::new(&GameConfig::new(20, 20)).unwrap();
+        game.food.clear();
+        for _ in 0..8 {
+            game_step(&mut game, &MockDisplay::new(), &MockInput::no_input());
+        }
+        assert_eq!(game.snakes[0].body.len(), 4);
+        assert_eq!(game.snakes[0].growth, 0);
+    }
+
+    #[test]
+    fn snake_grows_by_three_when_it_eats_food() {
+        let mut game = Game::new(&GameConfig::new(20, 20)).unwrap();
+        game.food = HashSet::from([Coordinate { x: 11, y: 10 }]);
+        assert!(game.advance().iter().any(|e| matches!(
+            e,
+            GameEvent::AteFood { id: 0, food, .. } if *food == Coordinate { x: 11, y: 10 }
+        )));
+        assert_eq!(game.snakes[0].growth, 5);
+        assert!(!game.food.contains(&Coordinate { x: 11, y: 10 }));
+    }
+
+    #[test]
+    fn eaten_food_respawns_on_a_free_cell() {
+        let mut game = Game::new(&GameConfig::new(20, 20)).unwrap();
+        game.food = HashSet::from([Coordinate { x: 11, y: 10 }]);
+        let spawned = game
+            .advance()
+            .into_iter()
+            .find_map(|e| match e {
+                GameEvent::AteFood { spawned, .. } => spawned,
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(game.food, HashSet::from([spawned.clone()]));
+        assert!(!game.snakes[0].body.contains(&spawned));
+        assert!(!spawned.out_of_bounds(&game));
+    }
+
+    #[test]
+    fn eating_food_scores_points() {
+        let mut game = Game::new(&GameConfig::new(20, 20)).unwrap();
+        game.food = HashSet::from([Coordinate { x: 11, y: 10 }]);
+        assert_eq!(points(&game.advance()), 12);
+        assert_eq!(
+            game.score(0),
+            Score {
+                food: 10,
+                length: 2,
+                survival: 0
+            }
         );
-        assert_eq!(game.growth, 5);
-        assert_eq!(counter, 0);
+        assert_eq!(game.snakes[0].score().total(), 12);
+    }
+
+    #[test]
+    fn surviving_scores_points() {
+        let mut game = Game::new(&GameConfig::new(40, 40).food(0).scoring(ScoreRules {
+            survival: 5,
+            survival_interval: 3,
+            ..ScoreRules::default()
+        }))
+        .unwrap();
+        let points: Vec<u32> = (0..6).map(|_| points(&game.advance())).collect();
+        assert_eq!(points, vec![0, 0, 5, 0, 0, 5]);
+        assert_eq!(game.score(0).total(), 10);
+    }
+
+    #[test]
+    fn dead_snake_stops_scoring() {
+        let mut game = Game::new(&GameConfig::new(3, 1).food(0).scoring(ScoreRules {
+            survival_interval: 1,
+            ..ScoreRules::default()
+        }))
+        .unwrap();
+        game.advance();
+        game.advance();
+        assert!(game.game_over);
+        assert_eq!(game.score(0).survival, 1);
+    }
+
+    #[test]
+    fn eating_speeds_up_the_game() {
+        let speed = Speed {
+            start: Duration::from_millis(200),
+            fastest: Duration::from_millis(100),
+            step: Duration::from_millis(25),
+            ramp: Ramp::Score(10),
+        };
+        let mut game = Game::new(&GameConfig::new(20, 20).speed(speed)).unwrap();
+        assert_eq!(game.level(), 0);
+        assert_eq!(game.tick_interval(), Duration::from_millis(200));
+        game.food = HashSet::from([Coordinate { x: 11, y: 10 }]);
+        assert!(game.advance().contains(&GameEvent::LevelUp { level: 1 }));
+        assert_eq!(game.level(), 1);
+        assert_eq!(game.tick_interval(), Duration::from_millis(175));
+    }
+
+    #[test]
+    fn level_follows_the_leading_snake() {
+        let speed = Speed {
+            ramp: Ramp::Length(2),
+            ..Speed::default()
+        };
+        let mut game = two_snakes((2, 2, Direction::East), (2, 8, Direction::East));
+        game.config.speed = speed;
+        game.snakes[1]
+            .body
+            .extend(vec![Coordinate { x: 0, y: 0 }; 7]);
+        assert_eq!(game.level(), 2);
+    }
+
+    #[test]
+    fn no_food_spawns_when_board_is_full() {
+        let mut game = Game::new(&GameConfig::new(2, 1)).unwrap();
+        assert_eq!(game.food, HashSet::from([Coordinate { x: 0, y: 0 }]));
+        game.turn(0, &Turn::Left);
+        game.turn(0, &Turn::Left);
+        assert!(game.advance().contains(&GameEvent::AteFood {
+            id: 0,
+            food: Coordinate { x: 0, y: 0 },
+            spawned: None,
+        }));
+        assert!(game.food.is_empty());
+    }
+
+    #[test_case(Direction::North, Coordinate { x: 10, y: 9 })]
+    #[test_case(Direction::South, Coordinate { x: 10, y: 11 })]
+    #[test_case(Direction::East, Coordinate { x: 11, y: 10 })]
+    fn absolute_input_sets_direction(d: Direction, head: Coordinate) {
+        let mut game = Game::new(&GameConfig::new(20, 20).food(0)).unwrap();
+        assert!(game.steer(0, &Input::Direction(d)));
+        assert_eq!(game.snakes[0].direction(), d);
+        game.advance();
+        assert_eq!(game.snakes[0].body.front(), Some(&head));
+    }
+
+    #[test]
+    fn absolute_input_cannot_reverse() {
+        let mut game = Game::new(&GameConfig::new(20, 20).food(0)).unwrap();
+        assert!(!game.steer(0, &Input::Direction(Direction::West)));
+        assert_eq!(game.snakes[0].direction(), Direction::East);
+        game.advance();
+        assert_eq!(
+            game.snakes[0].body.front(),
+            Some(&Coordinate { x: 11, y: 10 })
+        );
+    }
+
+    #[test_case(Input::Direction(Direction::North), Input::Direction(Direction::West) ; "absolute")]
+    #[test_case(Input::Turn(Turn::Left), Input::Turn(Turn::Left) ; "relative")]
+    fn two_inputs_in_one_tick_cannot_reverse(first: Input, second: Input) {
+        let mut game = Game::new(&GameConfig::new(20, 20).food(0)).unwrap();
+        assert!(game.steer(0, &first));
+        assert!(!game.steer(0, &second));
+        assert_eq!(game.snakes[0].direction(), Direction::North);
+        game.advance();
+        assert_eq!(
+            game.snakes[0].body.front(),
+            Some(&Coordinate { x: 10, y: 9 })
+        );
+    }
+
+    #[test]
+    fn relative_input_turns() {
+        let mut game = Game::new(&GameConfig::new(20, 20).food(0)).unwrap();
+        assert!(game.steer(0, &Input::Turn(Turn::Right)));
+        assert_eq!(game.snakes[0].direction(), Direction::South);
     }
 
     #[test]
     fn display_receives_updates() {
-        let mut game = Game::new(&20, &20);
-        let mut counter = 0;
+        let mut game = Game::new(&GameConfig::new(20, 20)).unwrap();
         let display = MockDisplay::new();
         for _ in 0..5 {
-            game_step(&mut counter, &mut game, &display, &MockInput::no_input());
+            game_step(&mut game, &display, &MockInput::no_input());
         }
         assert_eq!(
             *display.calls.borrow(),
             vec![
-                "update(SnakeChange { removed: None, added: Some(Coordinate { x: 11, y: 10 }) })",
-                "update(SnakeChange { removed: None, added: Some(Coordinate { x: 12, y: 10 }) })",
-                "update(SnakeChange { removed: None, added: Some(Coordinate { x: 13, y: 10 }) })",
-                "update(SnakeChange { removed: Some(Coordinate { x: 10, y: 10 }), added: Some(Coordinate { x: 14, y: 10 }) })",
-                "update(SnakeChange { removed: Some(Coordinate { x: 11, y: 10 }), added: Some(Coordinate { x: 15, y: 10 }) })"
+                "update([Moved { id: 0, head: Coordinate { x: 11, y: 10 }, tail: None }, Grew { id: 0, length: 2 }])",
+                "update([Moved { id: 0, head: Coordinate { x: 12, y: 10 }, tail: None }, Grew { id: 0, length: 3 }])",
+                "update([Moved { id: 0, head: Coordinate { x: 13, y: 10 }, tail: None }, Grew { id: 0, length: 4 }])",
+                "update([Moved { id: 0, head: Coordinate { x: 14, y: 10 }, tail: Some(Coordinate { x: 10, y: 10 }) }])",
+                "update([Moved { id: 0, head: Coordinate { x: 15, y: 10 }, tail: Some(Coordinate { x: 11, y: 10 }) }])",
             ]
         );
     }
 
     #[test]
     fn display_receives_game_over() {
-        let mut game = Game::new(&20, &20);
-        game.grow(&10);
-        let mut counter = 0;
+        let mut game = Game::new(&GameConfig::new(20, 20)).unwrap();
+        game.grow(0, &10);
         let display = MockDisplay::new();
         while !game.game_over {
             game_step(
-                &mut counter,
                 &mut game,
                 &display,
-                &MockInput::new(vec![Some(Turn::Left)]),
+                &MockInput::new(vec![Some(Input::Turn(Turn::Left))]),
             );
         }
         assert_eq!(
             *display.calls.borrow(),
             vec![
-                "update(SnakeChange { removed: None, added: Some(Coordinate { x: 10, y: 9 }) })",
-                "update(SnakeChange { removed: None, added: Some(Coordinate { x: 9, y: 9 }) })",
-                "update(SnakeChange { removed: None, added: Some(Coordinate { x: 9, y: 10 }) })",
-                "update(SnakeChange { removed: None, added: None })",
-                "game_over"
+                "update([Turned { id: 0, from: East, to: North }, Moved { id: 0, head: Coordinate { x: 10, y: 9 }, tail: None }, Grew { id: 0, length: 2 }])",
+                "update([Turned { id: 0, from: North, to: West }, Moved { id: 0, head: Coordinate { x: 9, y: 9 }, tail: None }, Grew { id: 0, length: 3 }])",
+                "update([Turned { id: 0, from: West, to: South }, Moved { id: 0, head: Coordinate { x: 9, y: 10 }, tail: None }, Grew { id: 0, length: 4 }])",
+                "update([Turned { id: 0, from: South, to: East }, HitSelf { id: 0, at: Coordinate { x: 10, y: 10 }, segment: 3, tail: None }, GameOver { winner: None, reason: SelfCollision { segment: 3 } }])",
+                "game_over",
             ]
         );
     }
 
+    #[test]
+    fn absolute_input_steers_snake() {
+        let mut game = Game::new(&GameConfig::new(20, 20).food(0)).unwrap();
+        let input = MockInput::new(vec![
+            Some(Input::Direction(Direction::South)),
+            Some(Input::Direction(Direction::North)),
+            Some(Input::Direction(Direction::West)),
+        ]);
+        for _ in 0..3 {
+            game_step(&mut game, &MockDisplay::new(), &input);
+        }
+        assert_eq!(
+            Vec::from(game.snakes[0].body.clone()),
+            vec![
+                Coordinate { x: 9, y: 12 },
+                Coordinate { x: 10, y: 12 },
+                Coordinate { x: 10, y: 11 },
+                Coordinate { x: 10, y: 10 },
+            ]
+        );
+    }
+
+    struct PerSnakeInput(Vec<Input>);
+
+    impl GameInput for PerSnakeInput {
+        fn poll(&self, id: SnakeId) -> Option<Input> {
+            self.0.get(id).cloned()
+        }
+    }
+
+    #[test]
+    fn game_step_polls_input_for_each_snake() {
+        let mut game = two_snakes((2, 2, Direction::East), (17, 17, Direction::West));
+        let input = PerSnakeInput(vec![
+            Input::Direction(Direction::South),
+            Input::Direction(Direction::North),
+        ]);
+        game_step(&mut game, &MockDisplay::new(), &input);
+        assert_eq!(game.snakes[0].head(), Some(&Coordinate { x: 2, y: 3 }));
+        assert_eq!(game.snakes[1].head(), Some(&Coordinate { x: 17, y: 16 }));
+    }
+
     #[test]
     fn input_steers_snake() {
-        let mut game = Game::new(&20, &20);
-        let mut counter = 0;
+        let mut game = Game::new(&GameConfig::new(20, 20)).unwrap();
         let display = MockDisplay::new();
         let input = MockInput::new(vec![
             None,
             None,
-            Some(Turn::Left),
+            Some(Input::Turn(Turn::Left)),
             None,
-            Some(Turn::Right),
+            Some(Input::Turn(Turn::Right)),
             None,
         ]);
         for _ in 0..7 {
-            game_step(&mut counter, &mut game, &display, &input);
+            game_step(&mut game, &display, &input);
         }
         assert_eq!(
             *display.calls.borrow(),
             vec![
-                "update(SnakeChange { removed: None, added: Some(Coordinate { x: 11, y: 10 }) })",
-                "update(SnakeChange { removed: None, added: Some(Coordinate { x: 12, y: 10 }) })",
-                "update(SnakeChange { removed: None, added: Some(Coordinate { x: 12, y: 9 }) })",
-                "update(SnakeChange { removed: Some(Coordinate { x: 10, y: 10 }), added: Some(Coordinate { x: 12, y: 8 }) })",
-                "update(SnakeChange { removed: Some(Coordinate { x: 11, y: 10 }), added: Some(Coordinate { x: 13, y: 8 }) })",
-                "update(SnakeChange { removed: Some(Coordinate { x: 12, y: 10 }), added: Some(Coordinate { x: 14, y: 8 }) })",
-                "update(SnakeChange { removed: Some(Coordinate { x: 12, y: 9 }), added: Some(Coordinate { x: 15, y: 8 }) })"
+                "update([Moved { id: 0, head: Coordinate { x: 11, y: 10 }, tail: None }, Grew { id: 0, length: 2 }])",
+                "update([Moved { id: 0, head: Coordinate { x: 12, y: 10 }, tail: None }, Grew { id: 0, length: 3 }])",
+                "update([Turned { id: 0, from: East, to: North }, Moved { id: 0, head: Coordinate { x: 12, y: 9 }, tail: None }, Grew { id: 0, length: 4 }])",
+                "update([Moved { id: 0, head: Coordinate { x: 12, y: 8 }, tail: Some(Coordinate { x: 10, y: 10 }) }])",
+                "update([Turned { id: 0, from: North, to: East }, Moved { id: 0, head: Coordinate { x: 13, y: 8 }, tail: Some(Coordinate { x: 11, y: 10 }) }])",
+                "update([Moved { id: 0, head: Coordinate { x: 14, y: 8 }, tail: Some(Coordinate { x: 12, y: 10 }) }])",
+                "update([Moved { id: 0, head: Coordinate { x: 15, y: 8 }, tail: Some(Coordinate { x: 12, y: 9 }) }])",
             ]
         );
     }
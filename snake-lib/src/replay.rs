@@ -0,0 +1,390 @@
+//! Recording and playing back games.
+//!
+//! A game is fully determined by its config (which includes the seed) and
+//! the input each snake received on each tick, so that is all a [`Replay`]
+//! stores. Each snake's inputs are kept as one string with a letter per
+//! tick — `N`, `S`, `E`, `W` for directions, `L`, `R` for turns and `.` for
+//! no input — and runs written with a count, so `12.N3.` is twelve quiet
+//! ticks, a turn north, then three more quiet ticks.
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fmt;
+
+use crate::{ConfigError, Direction, Game, GameConfig, GameInput, Input, SnakeId, Turn};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Replay {
+    pub config: GameConfig,
+    /// For each snake, what `poll` returned on every tick it was alive.
+    #[cfg_attr(feature = "serde", serde(with = "compact"))]
+    pub inputs: Vec<Vec<Option<Input>>>,
+}
+
+/// Most ticks one snake's inputs may decode to, over 55 hours at the
+/// default speed. Replays come from files and other people, so a huge count
+/// is refused rather than allocated.
+pub const MAX_INPUTS: usize = 1 << 20;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ReplayError {
+    UnknownInput {
+        position: usize,
+        symbol: char,
+    },
+    MissingInput {
+        position: usize,
+    },
+    /// Decoding would go past `MAX_INPUTS`.
+    TooLong {
+        position: usize,
+    },
+}
+
+/// Wraps another input, recording everything it returns.
+pub struct Recorder<I: GameInput> {
+    input: I,
+    replay: RefCell<Replay>,
+}
+
+/// Plays back the inputs from a replay. Once they run out it returns
+/// nothing, letting the snakes carry straight on.
+pub struct ReplayInput {
+    inputs: RefCell<Vec<VecDeque<Option<Input>>>>,
+}
+
+impl Replay {
+    pub fn new(config: &GameConfig) -> Replay {
+        Replay {
+            config: config.clone(),
+            inputs: vec![Vec::new(); config.snakes.len()],
+        }
+    }
+
+    /// A fresh game to play the replay back on.
+    pub fn game(&self) -> Result<Game, ConfigError> {
+        Game::new(&self.config)
+    }
+}
+
+impl<I: GameInput> Recorder<I> {
+    pub fn new(input: I, config: &GameConfig) -> Recorder<I> {
+        Recorder {
+            input,
+            replay: RefCell::new(Replay::new(config)),
+        }
+    }
+
+    /// Carries on an earlier recording, such as one saved with a game.
+    pub fn resume(input: I, replay: Replay) -> Recorder<I> {
+        Recorder {
+            input,
+            replay: RefCell::new(replay),
+        }
+    }
+
+    pub fn replay(&self) -> Replay {
+        self.replay.borrow().clone()
+    }
+
+    pub fn input(&self) -> &I {
+        &self.input
+    }
+
+    /// Drops inputs for the ticks `game` has been rewound past, so the
+    /// recording still plays back to the same game.
+    pub fn rewind(&self, game: &Game) {
+        let ticks = usize::try_from(game.ticks).unwrap_or(usize::MAX);
+        let mut replay = self.replay.borrow_mut();
+        for (inputs, snake) in replay.inputs.iter_mut().zip(&game.snakes) {
+            // Dead snakes stopped being polled before the rewound ticks.
+            if snake.alive {
+                inputs.truncate(ticks);
+            }
+        }
+    }
+
+    /// Throws away the recording so far and starts again for a new game.
+    pub fn reset(&self, config: &GameConfig) {
+        *self.replay.borrow_mut() = Replay::new(config);
+    }
+}
+
+impl<I: GameInput> GameInput for Recorder<I> {
+    fn poll(&self, id: SnakeId) -> Option<Input> {
+        let input = self.input.poll(id);
+        if let Some(inputs) = self.replay.borrow_mut().inputs.get_mut(id) {
+            inputs.push(input.clone());
+        }
+        input
+    }
+}
+
+impl ReplayInput {
+    pub fn new(replay: &Replay) -> ReplayInput {
+        ReplayInput {
+            inputs: RefCell::new(
+                replay
+                    .inputs
+                    .iter()
+                    .map(|i| i.iter().cloned().collect())
+                    .collect(),
+            ),
+        }
+    }
+
+    pub fn finished(&self) -> bool {
+        self.inputs.borrow().iter().all(|i| i.is_empty())
+    }
+}
+
+impl GameInput for ReplayInput {
+    fn poll(&self, id: SnakeId) -> Option<Input> {
+        self.inputs.borrow_mut().get_mut(id)?.pop_front().flatten()
+    }
+}
+
+fn symbol(input: &Option<Input>) -> char {
+    match input {
+        None => '.',
+        Some(Input::Direction(Direction::North)) => 'N',
+        Some(Input::Direction(Direction::South)) => 'S',
+        Some(Input::Direction(Direction::East)) => 'E',
+        Some(Input::Direction(Direction::West)) => 'W',
+        Some(Input::Turn(Turn::Left)) => 'L',
+        Some(Input::Turn(Turn::Right)) => 'R',
+    }
+}
+
+/// One snake's inputs in the compact form described in the module docs.
+pub fn encode(inputs: &[Option<Input>]) -> String {
+    let mut text = String::new();
+    let mut i = 0;
+    while i < inputs.len() {
+        let run = inputs[i..].iter().take_while(|x| **x == inputs[i]).count();
+        if run > 1 {
+            text.push_str(&run.to_string());
+        }
+        text.push(symbol(&inputs[i]));
+        i += run;
+    }
+    text
+}
+
+pub fn decode(text: &str) -> Result<Vec<Option<Input>>, ReplayError> {
+    let mut inputs = Vec::new();
+    let mut count: Option<usize> = None;
+    for (position, c) in text.chars().enumerate() {
+        if let Some(digit) = c.to_digit(10) {
+            count = count
+                .unwrap_or(0)
+                .checked_mul(10)
+                .and_then(|n| n.checked_add(digit as usize))
+                .filter(|n| *n <= MAX_INPUTS);
+            if count.is_none() {
+                return Err(ReplayError::TooLong { position });
+            }
+            continue;
+        }
+        let input = match c {
+            '.' => None,
+            'N' => Some(Input::Direction(Direction::North)),
+            'S' => Some(Input::Direction(Direction::South)),
+            'E' => Some(Input::Direction(Direction::East)),
+            'W' => Some(Input::Direction(Direction::West)),
+            'L' => Some(Input::Turn(Turn::Left)),
+            'R' => Some(Input::Turn(Turn::Right)),
+            _ => {
+                return Err(ReplayError::UnknownInput {
+                    position,
+                    symbol: c,
+                })
+            }
+        };
+        let run = count.take().unwrap_or(1);
+        if inputs.len() + run > MAX_INPUTS {
+            return Err(ReplayError::TooLong { position });
+        }
+        inputs.extend(std::iter::repeat_n(input, run));
+    }
+    match count {
+        Some(_) => Err(ReplayError::MissingInput {
+            position: text.chars().count(),
+        }),
+        None => Ok(inputs),
+    }
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::UnknownInput { position, symbol } => {
+                write!(f, "unknown input {:?} at position {}", symbol, position)
+            }
+            ReplayError::MissingInput { position } => {
+                write!(f, "count without an input at position {}", position)
+            }
+            ReplayError::TooLong { position } => write!(
+                f,
+                "more than {} inputs by position {}",
+                MAX_INPUTS, position
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+#[cfg(feature = "serde")]
+mod compact {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    use crate::Input;
+
+    pub fn serialize<S: Serializer>(
+        inputs: &[Vec<Option<Input>>],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(inputs.iter().map(|i| super::encode(i)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Vec<Option<Input>>>, D::Error> {
+        Vec::<String>::deserialize(deserializer)?
+            .iter()
+            .map(|text| super::decode(text).map_err(D::Error::custom))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::RefCell;
+
+    use crate::replay::{decode, encode, Recorder, ReplayError, ReplayInput};
+    use crate::{game_step, Direction, Game, GameConfig, GameInput, Input, NoDisplay};
+    use crate::{SnakeId, Turn};
+    use test_case::test_case;
+
+    /// Steers snake 0 from a script, one entry per tick.
+    struct Script(RefCell<Vec<Option<Input>>>);
+
+    impl GameInput for Script {
+        fn poll(&self, id: SnakeId) -> Option<Input> {
+            if id == 0 && !self.0.borrow().is_empty() {
+                self.0.borrow_mut().remove(0)
+            } else {
+                None
+            }
+        }
+    }
+
+    fn north() -> Option<Input> {
+        Some(Input::Direction(Direction::North))
+    }
+
+    #[test_case(vec![], "")]
+    #[test_case(vec![None], ".")]
+    #[test_case(vec![None, None, None, north(), None], "3.N.")]
+    #[test_case(vec![Some(Input::Turn(Turn::Left)), Some(Input::Turn(Turn::Left))], "2L")]
+    fn encodes_runs(inputs: Vec<Option<Input>>, expected: &str) {
+        assert_eq!(encode(&inputs), expected);
+        assert_eq!(decode(expected), Ok(inputs));
+    }
+
+    #[test]
+    fn decodes_multi_digit_counts() {
+        assert_eq!(decode("12.").unwrap().len(), 12);
+    }
+
+    #[test_case("3.X", ReplayError::UnknownInput { position: 2, symbol: 'X' })]
+    #[test_case("N12", ReplayError::MissingInput { position: 3 })]
+    #[test_case("99999999999999999999999N", ReplayError::TooLong { position: 6 })]
+    #[test_case("1048576.N", ReplayError::TooLong { position: 8 })]
+    fn bad_input_is_rejected(text: &str, error: ReplayError) {
+        assert_eq!(decode(text), Err(error));
+    }
+
+    #[test]
+    fn playback_reproduces_the_game() {
+        let config = GameConfig::new(20, 20).seed(7).food(3);
+        let mut script = vec![None; 30];
+        script[3] = north();
+        script[9] = Some(Input::Direction(Direction::West));
+        script[15] = Some(Input::Direction(Direction::South));
+        let recorder = Recorder::new(Script(RefCell::new(script)), &config);
+
+        let mut original = Game::new(&config).unwrap();
+        for _ in 0..30 {
+            game_step(&mut original, &NoDisplay, &recorder);
+        }
+
+        let replay = recorder.replay();
+        assert_eq!(replay.inputs[0].len(), original.ticks as usize);
+        let input = ReplayInput::new(&replay);
+        let mut played = replay.game().unwrap();
+        while !input.finished() {
+            game_step(&mut played, &NoDisplay, &input);
+        }
+
+        assert_eq!(played.snakes[0].body, original.snakes[0].body);
+        assert_eq!(played.food, original.food);
+        assert_eq!(played.score(0), original.score(0));
+    }
+
+    #[test]
+    fn rewound_recording_plays_back_to_the_same_game() {
+        let config = GameConfig::new(20, 20).seed(7).food(3);
+        let mut script = vec![None; 30];
+        script[3] = north();
+        // Undone by the rewind.
+        script[12] = Some(Input::Direction(Direction::East));
+        script[17] = Some(Input::Direction(Direction::West));
+        let recorder = Recorder::new(Script(RefCell::new(script)), &config);
+
+        let mut original = Game::new(&config).unwrap();
+        for _ in 0..15 {
+            game_step(&mut original, &NoDisplay, &recorder);
+        }
+        original.rewind(6);
+        recorder.rewind(&original);
+        for _ in 0..15 {
+            game_step(&mut original, &NoDisplay, &recorder);
+        }
+
+        let replay = recorder.replay();
+        assert!(!original.game_over);
+        assert_eq!(replay.inputs[0].len(), 24);
+        let input = ReplayInput::new(&replay);
+        let mut played = replay.game().unwrap();
+        while !input.finished() {
+            game_step(&mut played, &NoDisplay, &input);
+        }
+        assert_eq!(played.snakes[0].body, original.snakes[0].body);
+    }
+
+    #[test]
+    fn dead_snakes_stop_recording() {
+        let config = GameConfig::new(3, 3);
+        let recorder = Recorder::new(Script(RefCell::new(vec![])), &config);
+        let mut game = Game::new(&config).unwrap();
+        for _ in 0..5 {
+            game_step(&mut game, &NoDisplay, &recorder);
+        }
+        assert_eq!(recorder.replay().inputs[0].len(), 2);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn replay_round_trips_through_json() {
+        use crate::replay::Replay;
+
+        let mut replay = Replay::new(&GameConfig::new(10, 10));
+        replay.inputs[0] = vec![None, None, north()];
+        let json = serde_json::to_string(&replay).unwrap();
+        assert!(json.contains("\"inputs\":[\"2.N\"]"));
+        assert_eq!(serde_json::from_str::<Replay>(&json).unwrap(), replay);
+    }
+}
@@ -0,0 +1,214 @@
+//! Loading saved games. `Game` derives `Serialize` directly; loading needs
+//! a hand-written `Deserialize` so the cells the snakes occupy, which aren't
+//! saved, can be rebuilt from their bodies. It also turns away saves that
+//! couldn't come from a real game, as `advance` assumes they can't happen.
+
+use std::collections::{HashSet, VecDeque};
+
+use serde::de::Error;
+use serde::{Deserialize, Deserializer};
+
+use crate::{Coordinate, Game, GameConfig, GameOverReason, Rng, Snake, SnakeId};
+
+#[derive(Deserialize)]
+struct SavedGame {
+    snakes: Vec<Snake>,
+    food: HashSet<Coordinate>,
+    obstacles: HashSet<Coordinate>,
+    width: u16,
+    height: u16,
+    ticks: u32,
+    config: GameConfig,
+    rng: Rng,
+    game_over: bool,
+    #[serde(default)]
+    game_over_reason: Option<GameOverReason>,
+    winner: Option<SnakeId>,
+}
+
+impl<'de> Deserialize<'de> for Game {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Game, D::Error> {
+        let saved = SavedGame::deserialize(deserializer)?;
+        let game = Game {
+            active_cells: saved
+                .snakes
+                .iter()
+                .flat_map(|s| s.body.iter().cloned())
+                .collect(),
+            snakes: saved.snakes,
+            history: VecDeque::new(),
+            food: saved.food,
+            obstacles: saved.obstacles,
+            width: saved.width,
+            height: saved.height,
+            ticks: saved.ticks,
+            config: saved.config,
+            rng: saved.rng,
+            game_over: saved.game_over,
+            game_over_reason: saved.game_over_reason,
+            winner: saved.winner,
+        };
+        check(&game).map_err(D::Error::custom)?;
+        Ok(game)
+    }
+}
+
+/// Why a loaded game couldn't have been saved from a real one, if it couldn't.
+fn check(game: &Game) -> Result<(), String> {
+    let config = &game.config;
+    config
+        .validate()
+        .map_err(|e| format!("invalid config: {}", e))?;
+    if (game.width, game.height) != (config.width, config.height) {
+        return Err(format!(
+            "board is {}x{}, config is {}x{}",
+            game.width, game.height, config.width, config.height
+        ));
+    }
+    if game.obstacles != config.obstacles {
+        return Err("obstacles don't match the config".to_string());
+    }
+    if game.snakes.len() != config.snakes.len() {
+        return Err(format!(
+            "{} snakes, config has {}",
+            game.snakes.len(),
+            config.snakes.len()
+        ));
+    }
+    if let Some((i, snake)) = game.snakes.iter().enumerate().find(|(i, s)| s.id != *i) {
+        return Err(format!("snake {} has id {}", i, snake.id));
+    }
+    if let Some(winner) = game.winner.filter(|w| *w >= game.snakes.len()) {
+        return Err(format!("winner {} is not a snake", winner));
+    }
+    if let Some(snake) = game.snakes.iter().find(|s| s.alive && s.body.is_empty()) {
+        return Err(format!("snake {} is alive with no body", snake.id));
+    }
+    let cells = game
+        .snakes
+        .iter()
+        .flat_map(|s| s.body.iter())
+        .chain(&game.food)
+        .chain(&game.obstacles);
+    let mut seen = HashSet::new();
+    for c in cells {
+        if c.x < 0 || c.x >= game.width.into() || c.y < 0 || c.y >= game.height.into() {
+            return Err(format!("{:?} is off the board", c));
+        }
+        if !seen.insert(c) {
+            return Err(format!("{:?} is used twice", c));
+        }
+    }
+    // There can be less food than configured only once the board fills up.
+    let food = game.food.len();
+    if food > game.config.food || (food < game.config.food && !game.free_cells().is_empty()) {
+        return Err(format!(
+            "{} food on the board, expected {}",
+            food, game.config.food
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Coordinate, Direction, Game, GameConfig, GameOverReason, Input};
+    use test_case::test_case;
+
+    fn reload(game: &Game) -> Game {
+        serde_json::from_str(&serde_json::to_string(game).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn loaded_game_matches_saved_game() {
+        let mut game = Game::new(&GameConfig::new(20, 20).seed(3).food(4)).unwrap();
+        for _ in 0..6 {
+            game.advance();
+        }
+        let loaded = reload(&game);
+        assert_eq!(loaded.active_cells, game.active_cells);
+        assert_eq!(loaded.snakes[0].body, game.snakes[0].body);
+        assert_eq!(loaded.snakes[0].direction(), game.snakes[0].direction());
+        assert_eq!(loaded.snakes[0].growth, game.snakes[0].growth);
+        assert_eq!(loaded.food, game.food);
+        assert_eq!(loaded.ticks, game.ticks);
+        assert_eq!(loaded.rng, game.rng);
+    }
+
+    #[test]
+    fn loaded_game_plays_on_identically() {
+        let mut game = Game::new(&GameConfig::new(20, 20).seed(11).food(5)).unwrap();
+        game.steer(0, &Input::Direction(Direction::North));
+        for _ in 0..4 {
+            game.advance();
+        }
+        let mut loaded = reload(&game);
+        for game in [&mut game, &mut loaded] {
+            game.steer(0, &Input::Direction(Direction::West));
+            for _ in 0..12 {
+                game.advance();
+            }
+        }
+        assert_eq!(loaded.snakes[0].body, game.snakes[0].body);
+        assert_eq!(loaded.food, game.food);
+        assert_eq!(loaded.score(0), game.score(0));
+        assert_eq!(loaded.game_over, game.game_over);
+    }
+
+    #[test]
+    fn loaded_snake_still_collides_with_itself() {
+        let mut game = Game::new(&GameConfig::new(20, 20).length(5).food(0)).unwrap();
+        for _ in 0..5 {
+            game.advance();
+        }
+        let mut loaded = reload(&game);
+        for turn in [Direction::North, Direction::West, Direction::South] {
+            loaded.steer(0, &Input::Direction(turn));
+            loaded.advance();
+        }
+        assert!(loaded.game_over);
+        assert_eq!(
+            loaded.game_over_reason,
+            Some(GameOverReason::SelfCollision { segment: 3 })
+        );
+        assert!(loaded.active_cells.contains(&Coordinate { x: 14, y: 10 }));
+    }
+
+    #[test_case(|g| g.snakes[0].body.clear(), "no body"; "live snake without a body")]
+    #[test_case(|g| g.snakes[0].body[0].x = 20, "off the board"; "snake off the board")]
+    #[test_case(|g| { g.food.insert(Coordinate { x: -1, y: 0 }); }, "off the board"; "food off the board")]
+    #[test_case(|g| g.snakes[0].body.push_back(Coordinate { x: 10, y: 10 }), "used twice"; "snake crossing itself")]
+    #[test_case(|g| { g.food.insert(Coordinate { x: 10, y: 10 }); }, "used twice"; "food under the snake")]
+    #[test_case(|g| g.food.clear(), "expected 2"; "missing food")]
+    #[test_case(|g| g.snakes[0].id = 3, "has id 3"; "snake id out of place")]
+    #[test_case(|g| g.winner = Some(1), "not a snake"; "winner out of range")]
+    #[test_case(|g| g.config.length = 0, "invalid config"; "invalid config")]
+    #[test_case(|g| g.config.snakes.clear(), "invalid config"; "config without snakes")]
+    #[test_case(|g| g.width = 21, "board is 21x20"; "board size differs")]
+    #[test_case(|g| { g.obstacles.insert(Coordinate { x: 0, y: 0 }); }, "obstacles"; "obstacles differ")]
+    #[test_case(
+        |g| g.config = g.config.clone().add_snake(Coordinate { x: 2, y: 2 }, Direction::East),
+        "1 snakes, config has 2";
+        "snake count differs"
+    )]
+    fn impossible_saves_do_not_load(spoil: fn(&mut Game), message: &str) {
+        let mut game = Game::new(
+            &GameConfig::new(20, 20)
+                .start(Coordinate { x: 10, y: 10 })
+                .food(2),
+        )
+        .unwrap();
+        spoil(&mut game);
+        let json = serde_json::to_string(&game).unwrap();
+        let error = serde_json::from_str::<Game>(&json).err().unwrap();
+        assert!(error.to_string().contains(message), "{}", error);
+    }
+
+    #[test]
+    fn full_board_may_be_short_of_food() {
+        let mut game = Game::new(&GameConfig::new(2, 1).food(3)).unwrap();
+        assert_eq!(game.food.len(), 1);
+        game = reload(&game);
+        assert_eq!(game.food.len(), 1);
+    }
+}
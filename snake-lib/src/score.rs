@@ -0,0 +1,128 @@
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ScoreRules {
+    /// Points for each piece of food eaten.
+    pub per_food: u32,
+    /// Extra points per segment of the snake's length when it eats, so food
+    /// is worth more to a longer snake.
+    pub per_segment: u32,
+    /// Points for staying alive, awarded every `survival_interval` ticks.
+    pub survival: u32,
+    pub survival_interval: u32,
+}
+
+/// Points a snake has earned, split by where they came from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Score {
+    pub food: u32,
+    pub length: u32,
+    pub survival: u32,
+}
+
+impl Default for ScoreRules {
+    fn default() -> ScoreRules {
+        ScoreRules {
+            per_food: 10,
+            per_segment: 1,
+            survival: 1,
+            survival_interval: 10,
+        }
+    }
+}
+
+impl ScoreRules {
+    /// Points for eating with a snake `length` segments long. Rules can come
+    /// from saved configs, so scores stop at `u32::MAX` rather than overflow.
+    pub fn eat(&self, score: &mut Score, length: usize) -> u32 {
+        let bonus = self
+            .per_segment
+            .saturating_mul(u32::try_from(length).unwrap_or(u32::MAX));
+        score.food = score.food.saturating_add(self.per_food);
+        score.length = score.length.saturating_add(bonus);
+        self.per_food.saturating_add(bonus)
+    }
+
+    /// Points for surviving to `tick`.
+    pub fn survive(&self, score: &mut Score, tick: u32) -> u32 {
+        if self.survival_interval > 0 && tick.is_multiple_of(self.survival_interval) {
+            score.survival = score.survival.saturating_add(self.survival);
+            self.survival
+        } else {
+            0
+        }
+    }
+}
+
+impl Score {
+    pub fn total(&self) -> u32 {
+        self.food
+            .saturating_add(self.length)
+            .saturating_add(self.survival)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Score, ScoreRules};
+
+    #[test]
+    fn total_adds_all_sources() {
+        let score = Score {
+            food: 20,
+            length: 7,
+            survival: 3,
+        };
+        assert_eq!(score.total(), 30);
+    }
+
+    #[test]
+    fn eating_scores_food_and_length_bonus() {
+        let rules = ScoreRules::default();
+        let mut score = Score::default();
+        assert_eq!(rules.eat(&mut score, 5), 15);
+        assert_eq!(
+            score,
+            Score {
+                food: 10,
+                length: 5,
+                survival: 0
+            }
+        );
+    }
+
+    #[test]
+    fn huge_rules_saturate() {
+        let rules = ScoreRules {
+            per_food: u32::MAX,
+            per_segment: u32::MAX,
+            survival: u32::MAX,
+            survival_interval: 1,
+        };
+        let mut score = Score::default();
+        for tick in 1..=2 {
+            assert_eq!(rules.eat(&mut score, 5), u32::MAX);
+            assert_eq!(rules.survive(&mut score, tick), u32::MAX);
+        }
+        assert_eq!(score.total(), u32::MAX);
+    }
+
+    #[test]
+    fn survival_scores_on_interval() {
+        let rules = ScoreRules::default();
+        let mut score = Score::default();
+        let points: u32 = (1..=25).map(|t| rules.survive(&mut score, t)).sum();
+        assert_eq!(points, 2);
+        assert_eq!(score.survival, 2);
+    }
+
+    #[test]
+    fn zero_interval_never_scores_survival() {
+        let rules = ScoreRules {
+            survival_interval: 0,
+            ..ScoreRules::default()
+        };
+        let mut score = Score::default();
+        assert_eq!(rules.survive(&mut score, 10), 0);
+    }
+}
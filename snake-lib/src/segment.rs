@@ -0,0 +1,168 @@
+//! The shape of each piece of a snake, for front ends that draw more than a
+//! filled cell.
+//!
+//! Shapes come from neighbouring cells of the body, so a snake that has
+//! just wrapped around the board still joins up across the edge.
+
+use crate::{Coordinate, Direction, Game, SnakeId, Walls};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Segment {
+    /// Facing the way the snake is heading.
+    Head(Direction),
+    /// Travelled through without turning, the snake moving in the direction.
+    Straight(Direction),
+    /// Entered moving `from` and left moving `to`.
+    Corner { from: Direction, to: Direction },
+    /// Pointing the way the tail will move next.
+    Tail(Direction),
+}
+
+impl Segment {
+    /// The sides of its cell a segment joins the rest of the body through.
+    /// A head always has a neck side, even on a snake of one segment.
+    pub fn sides(&self) -> Vec<Direction> {
+        match self {
+            Segment::Head(facing) => vec![facing.opposite()],
+            Segment::Straight(d) => vec![d.opposite(), *d],
+            Segment::Corner { from, to } => vec![from.opposite(), *to],
+            Segment::Tail(d) => vec![*d],
+        }
+    }
+}
+
+impl Game {
+    /// Which way a snake moves to get from one cell to its neighbour, across
+    /// the edge if the walls wrap. `None` if the cells don't touch.
+    pub fn direction_between(&self, from: &Coordinate, to: &Coordinate) -> Option<Direction> {
+        Direction::ALL.into_iter().find(|d| {
+            let next = from.advance(d);
+            match self.config.walls {
+                Walls::Wrap => next.wrap(self) == *to,
+                Walls::Solid => next == *to,
+            }
+        })
+    }
+
+    /// One segment for each cell of a snake's body, head first.
+    pub fn segments(&self, id: SnakeId) -> Vec<Segment> {
+        let snake = &self.snakes[id];
+        let len = snake.body.len();
+        // Cells that don't touch, such as a head stuck in a wall, count as
+        // heading the way the snake last moved.
+        let travel = |from: &Coordinate, to: &Coordinate| {
+            self.direction_between(from, to).unwrap_or(snake.heading)
+        };
+        snake
+            .body
+            .iter()
+            .enumerate()
+            .map(|(i, c)| {
+                if i == 0 {
+                    return Segment::Head(snake.heading);
+                }
+                let to = travel(c, &snake.body[i - 1]);
+                if i == len - 1 {
+                    return Segment::Tail(to);
+                }
+                let from = travel(&snake.body[i + 1], c);
+                if from == to || from == to.opposite() {
+                    Segment::Straight(to)
+                } else {
+                    Segment::Corner { from, to }
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::segment::Segment;
+    use crate::{Coordinate, Direction, Game, GameConfig, Input, Walls};
+    use std::collections::{HashSet, VecDeque};
+    use test_case::test_case;
+
+    fn game_with_body(walls: Walls, body: &[(i32, i32)]) -> Game {
+        let mut game = Game::new(&GameConfig::new(10, 10).walls(walls)).unwrap();
+        game.snakes[0].body = body.iter().map(|&(x, y)| Coordinate { x, y }).collect();
+        game
+    }
+
+    #[test]
+    fn new_snake_is_all_head() {
+        let game = Game::new(&GameConfig::new(10, 10)).unwrap();
+        assert_eq!(
+            game.segments(0),
+            vec![Segment::Head(game.snakes[0].direction())]
+        );
+    }
+
+    #[test]
+    fn body_follows_the_path() {
+        let mut game = Game::new(&GameConfig::new(10, 10)).unwrap();
+        game.food = HashSet::new();
+        game.snakes[0].body = VecDeque::from([Coordinate { x: 5, y: 5 }]);
+        game.grow(0, &3);
+        for direction in [Direction::East, Direction::East, Direction::South] {
+            game.steer(0, &Input::Direction(direction));
+            game.advance();
+        }
+        assert_eq!(
+            game.segments(0),
+            vec![
+                Segment::Head(Direction::South),
+                Segment::Corner {
+                    from: Direction::East,
+                    to: Direction::South
+                },
+                Segment::Straight(Direction::East),
+                Segment::Tail(Direction::East),
+            ]
+        );
+        // Steering changes where it goes next, not the shape it has now.
+        game.steer(0, &Input::Direction(Direction::West));
+        assert_eq!(game.segments(0)[0], Segment::Head(Direction::South));
+    }
+
+    #[test_case(Walls::Wrap, Segment::Straight(Direction::East); "joins across the edge")]
+    #[test_case(
+        Walls::Solid,
+        Segment::Corner { from: Direction::North, to: Direction::East };
+        "falls back to the heading"
+    )]
+    fn body_across_the_edge(walls: Walls, middle: Segment) {
+        let mut game = game_with_body(walls, &[(1, 3), (0, 3), (9, 3)]);
+        game.snakes[0].heading = Direction::North;
+        assert_eq!(game.segments(0)[1], middle);
+    }
+
+    #[test]
+    fn direction_between_neighbours_only() {
+        let game = game_with_body(Walls::Solid, &[(0, 0)]);
+        let at = |x, y| Coordinate { x, y };
+        assert_eq!(
+            game.direction_between(&at(2, 2), &at(2, 1)),
+            Some(Direction::North)
+        );
+        assert_eq!(game.direction_between(&at(2, 2), &at(3, 3)), None);
+        assert_eq!(game.direction_between(&at(0, 2), &at(9, 2)), None);
+    }
+
+    #[test]
+    fn sides_join_neighbouring_cells() {
+        let corner = Segment::Corner {
+            from: Direction::North,
+            to: Direction::East,
+        };
+        assert_eq!(corner.sides(), vec![Direction::South, Direction::East]);
+        assert_eq!(
+            Segment::Head(Direction::West).sides(),
+            vec![Direction::East]
+        );
+        assert_eq!(
+            Segment::Tail(Direction::North).sides(),
+            vec![Direction::North]
+        );
+    }
+}
@@ -0,0 +1,271 @@
+//! Running games without a display, for bots and for trying out settings.
+//!
+//! ```
+//! use snake::sim::{Observation, Simulator};
+//! use snake::{GameConfig, Input};
+//!
+//! // An agent that never steers.
+//! let stats = Simulator::new(GameConfig::new(20, 20))
+//!     .run_many(100, |_: &Observation| -> Option<Input> { None });
+//! assert_eq!(stats.games.len(), 100);
+//! ```
+
+use crate::{
+    Coordinate, Direction, Game, GameConfig, GameEvent, GameOverReason, Input, Snake, SnakeId,
+    Walls,
+};
+
+/// What an agent gets to see each tick: the whole game, from the point of
+/// view of one snake.
+pub struct Observation<'a> {
+    pub id: SnakeId,
+    pub game: &'a Game,
+}
+
+/// Steers snakes from observations. Closures taking an `&Observation` and
+/// returning `Option<Input>` are agents.
+pub trait Agent {
+    /// Called once per tick for each live snake.
+    fn act(&mut self, observation: &Observation) -> Option<Input>;
+}
+
+impl<F: FnMut(&Observation) -> Option<Input>> Agent for F {
+    fn act(&mut self, observation: &Observation) -> Option<Input> {
+        self(observation)
+    }
+}
+
+pub struct Simulator {
+    pub config: GameConfig,
+    /// Games still going after this many ticks are stopped and counted as
+    /// timed out, so an agent that circles forever can't hang a run.
+    pub max_ticks: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GameStats {
+    pub seed: u64,
+    pub ticks: u32,
+    /// Per snake.
+    pub scores: Vec<u32>,
+    pub lengths: Vec<usize>,
+    pub food_eaten: Vec<u32>,
+    pub winner: Option<SnakeId>,
+    pub timed_out: bool,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub games: Vec<GameStats>,
+}
+
+impl<'a> Observation<'a> {
+    pub fn snake(&self) -> &'a Snake {
+        &self.game.snakes[self.id]
+    }
+
+    pub fn head(&self) -> &'a Coordinate {
+        self.snake().head().expect("live snakes have a head")
+    }
+
+    /// The cell next to `from` in `direction`, wrapping around the board if
+    /// the walls do.
+    pub fn neighbour(&self, from: &Coordinate, direction: Direction) -> Coordinate {
+        let next = from.advance(&direction);
+        match self.game.config.walls {
+            Walls::Wrap => next.wrap(self.game),
+            Walls::Solid => next,
+        }
+    }
+
+    /// Whether a snake could move into `c` this tick: not blocked, or the
+    /// tail of a snake that isn't growing, which will have moved on.
+    pub fn open(&self, c: &Coordinate) -> bool {
+        !self.blocked(c)
+            || (!c.out_of_bounds(self.game)
+                && !self.game.obstacles.contains(c)
+                && self
+                    .game
+                    .snakes
+                    .iter()
+                    .any(|s| s.alive && s.growth == 0 && s.body.back() == Some(c)))
+    }
+
+    /// Whether moving into `c` would crash right now: off the board, a wall
+    /// or any snake. Tails that are about to move still count.
+    pub fn blocked(&self, c: &Coordinate) -> bool {
+        c.out_of_bounds(self.game)
+            || self.game.obstacles.contains(c)
+            || self.game.active_cells.contains(c)
+    }
+}
+
+impl Simulator {
+    pub fn new(config: GameConfig) -> Simulator {
+        Simulator {
+            config,
+            max_ticks: 10_000,
+        }
+    }
+
+    pub fn max_ticks(mut self, max_ticks: u32) -> Simulator {
+        self.max_ticks = max_ticks;
+        self
+    }
+
+    /// Plays one game with the config's seed. Panics if the config is
+    /// invalid.
+    pub fn run<A: Agent>(&self, agent: &mut A) -> GameStats {
+        let mut game = Game::new(&self.config).expect("invalid simulator config");
+        let mut food_eaten = vec![0; game.snakes.len()];
+        while !game.game_over && game.ticks < self.max_ticks {
+            for id in 0..game.snakes.len() {
+                if game.snakes[id].alive {
+                    if let Some(input) = agent.act(&Observation { id, game: &game }) {
+                        game.steer(id, &input);
+                    }
+                }
+            }
+            for event in game.advance() {
+                if let GameEvent::AteFood { id, .. } = event {
+                    food_eaten[id] += 1;
+                }
+            }
+        }
+        game.end(GameOverReason::Timeout);
+        GameStats {
+            seed: self.config.seed,
+            ticks: game.ticks,
+            scores: game.snakes.iter().map(|s| s.score().total()).collect(),
+            lengths: game.snakes.iter().map(|s| s.len()).collect(),
+            food_eaten,
+            winner: game.winner,
+            timed_out: game.game_over_reason == Some(GameOverReason::Timeout),
+        }
+    }
+
+    /// Plays `games` games with consecutive seeds starting from the
+    /// config's.
+    pub fn run_many<A: Agent>(&self, games: usize, mut agent: A) -> Stats {
+        let games = (0..games as u64)
+            .map(|i| {
+                Simulator {
+                    config: self.config.clone().seed(self.config.seed.wrapping_add(i)),
+                    max_ticks: self.max_ticks,
+                }
+                .run(&mut agent)
+            })
+            .collect();
+        Stats { games }
+    }
+}
+
+impl Stats {
+    /// Mean of the first snake's score.
+    pub fn mean_score(&self) -> f64 {
+        self.mean(|g| g.scores[0].into())
+    }
+
+    pub fn best_score(&self) -> u32 {
+        self.games.iter().map(|g| g.scores[0]).max().unwrap_or(0)
+    }
+
+    pub fn mean_length(&self) -> f64 {
+        self.mean(|g| g.lengths[0] as f64)
+    }
+
+    pub fn mean_ticks(&self) -> f64 {
+        self.mean(|g| g.ticks.into())
+    }
+
+    pub fn timeouts(&self) -> usize {
+        self.games.iter().filter(|g| g.timed_out).count()
+    }
+
+    /// How many games each snake won, for multi-snake configs.
+    pub fn wins(&self, id: SnakeId) -> usize {
+        self.games.iter().filter(|g| g.winner == Some(id)).count()
+    }
+
+    fn mean(&self, f: impl Fn(&GameStats) -> f64) -> f64 {
+        if self.games.is_empty() {
+            return 0.0;
+        }
+        self.games.iter().map(f).sum::<f64>() / self.games.len() as f64
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::sim::{Observation, Simulator};
+    use crate::{Direction, GameConfig, Input};
+
+    fn idle(_: &Observation) -> Option<Input> {
+        None
+    }
+
+    /// Turns whenever the cell ahead is blocked, preferring right.
+    fn avoid_walls(o: &Observation) -> Option<Input> {
+        let heading = o.snake().direction();
+        let ahead = o.neighbour(o.head(), heading);
+        if !o.blocked(&ahead) {
+            return None;
+        }
+        Direction::ALL
+            .into_iter()
+            .filter(|d| *d != heading.opposite())
+            .find(|d| !o.blocked(&o.neighbour(o.head(), *d)))
+            .map(Input::Direction)
+    }
+
+    #[test]
+    fn idle_snake_hits_the_wall() {
+        let stats = Simulator::new(GameConfig::new(20, 20).food(0)).run(&mut idle);
+        assert_eq!(stats.ticks, 10);
+        assert!(!stats.timed_out);
+        assert_eq!(stats.food_eaten, vec![0]);
+    }
+
+    #[test]
+    fn long_games_time_out() {
+        let stats = Simulator::new(GameConfig::new(20, 20).food(0).length(1))
+            .max_ticks(500)
+            .run(&mut avoid_walls);
+        assert_eq!(stats.ticks, 500);
+        assert!(stats.timed_out);
+    }
+
+    #[test]
+    fn each_game_gets_its_own_seed() {
+        let stats = Simulator::new(GameConfig::new(20, 20).seed(40)).run_many(3, idle);
+        let seeds: Vec<u64> = stats.games.iter().map(|g| g.seed).collect();
+        assert_eq!(seeds, vec![40, 41, 42]);
+    }
+
+    #[test]
+    fn runs_are_repeatable() {
+        let sim = Simulator::new(GameConfig::new(15, 15).food(3)).max_ticks(2000);
+        assert_eq!(sim.run_many(20, avoid_walls), sim.run_many(20, avoid_walls));
+    }
+
+    #[test]
+    fn agent_sees_its_own_snake() {
+        let config = GameConfig::versus(20, 20, 2).food(0);
+        let mut seen = Vec::new();
+        Simulator::new(config)
+            .max_ticks(1)
+            .run(&mut |o: &Observation| {
+                seen.push((o.id, o.snake().direction()));
+                None
+            });
+        assert_eq!(seen, vec![(0, Direction::East), (1, Direction::West)]);
+    }
+
+    #[test]
+    fn stats_summarise_games() {
+        let stats = Simulator::new(GameConfig::new(20, 20).food(0)).run_many(4, idle);
+        assert_eq!(stats.mean_ticks(), 10.0);
+        assert_eq!(stats.mean_score(), 0.0);
+        assert_eq!(stats.timeouts(), 0);
+    }
+}
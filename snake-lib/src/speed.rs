@@ -0,0 +1,160 @@
+use std::time::Duration;
+
+/// What makes the game speed up.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Ramp {
+    /// Never speed up.
+    Constant,
+    /// One level for every this many points.
+    Score(u32),
+    /// One level for every this many segments beyond the starting length.
+    Length(u32),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Speed {
+    /// Tick interval at level zero.
+    pub start: Duration,
+    /// The interval never drops below this.
+    pub fastest: Duration,
+    /// How much shorter the interval gets each level.
+    pub step: Duration,
+    pub ramp: Ramp,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Default for Speed {
+    fn default() -> Speed {
+        Difficulty::Normal.speed()
+    }
+}
+
+impl Speed {
+    pub fn constant(interval: Duration) -> Speed {
+        Speed {
+            start: interval,
+            fastest: interval,
+            step: Duration::ZERO,
+            ramp: Ramp::Constant,
+        }
+    }
+
+    /// The level reached with `score` points by a snake that has grown
+    /// `grown` segments.
+    pub fn level(&self, score: u32, grown: u32) -> u32 {
+        let level = match self.ramp {
+            Ramp::Constant | Ramp::Score(0) | Ramp::Length(0) => 0,
+            Ramp::Score(points) => score / points,
+            Ramp::Length(segments) => grown / segments,
+        };
+        level.min(self.max_level())
+    }
+
+    /// The first level at which the game is running at its fastest.
+    pub fn max_level(&self) -> u32 {
+        if self.step.is_zero() {
+            return 0;
+        }
+        let range = self.start.saturating_sub(self.fastest);
+        let levels = range.as_nanos().div_ceil(self.step.as_nanos());
+        u32::try_from(levels).unwrap_or(u32::MAX)
+    }
+
+    pub fn interval(&self, level: u32) -> Duration {
+        self.start
+            .saturating_sub(self.step.saturating_mul(level))
+            .max(self.fastest.min(self.start))
+    }
+}
+
+impl Difficulty {
+    pub fn speed(&self) -> Speed {
+        let ms = Duration::from_millis;
+        match self {
+            Difficulty::Easy => Speed {
+                start: ms(250),
+                fastest: ms(150),
+                step: ms(10),
+                ramp: Ramp::Score(100),
+            },
+            Difficulty::Normal => Speed {
+                start: ms(200),
+                fastest: ms(90),
+                step: ms(10),
+                ramp: Ramp::Score(50),
+            },
+            Difficulty::Hard => Speed {
+                start: ms(150),
+                fastest: ms(50),
+                step: ms(10),
+                ramp: Ramp::Score(25),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use crate::{Difficulty, Ramp, Speed};
+    use test_case::test_case;
+
+    fn speed(ramp: Ramp) -> Speed {
+        Speed {
+            start: Duration::from_millis(200),
+            fastest: Duration::from_millis(100),
+            step: Duration::from_millis(20),
+            ramp,
+        }
+    }
+
+    #[test_case(Ramp::Score(50), 0, 0, 0)]
+    #[test_case(Ramp::Score(50), 49, 9, 0)]
+    #[test_case(Ramp::Score(50), 100, 0, 2)]
+    #[test_case(Ramp::Length(3), 100, 7, 2)]
+    #[test_case(Ramp::Constant, 1000, 100, 0)]
+    #[test_case(Ramp::Score(0), 1000, 100, 0 ; "zero points per level")]
+    #[test_case(Ramp::Score(10), 1000, 0, 5 ; "capped at fastest")]
+    fn level_follows_ramp(ramp: Ramp, score: u32, grown: u32, expected: u32) {
+        assert_eq!(speed(ramp).level(score, grown), expected);
+    }
+
+    #[test_case(0, 200)]
+    #[test_case(2, 160)]
+    #[test_case(5, 100)]
+    #[test_case(9, 100)]
+    fn interval_shrinks_to_fastest(level: u32, expected: u64) {
+        assert_eq!(
+            speed(Ramp::Score(50)).interval(level),
+            Duration::from_millis(expected)
+        );
+    }
+
+    #[test]
+    fn constant_speed_never_changes() {
+        let speed = Speed::constant(Duration::from_millis(120));
+        assert_eq!(speed.max_level(), 0);
+        assert_eq!(
+            speed.interval(speed.level(5000, 50)),
+            Duration::from_millis(120)
+        );
+    }
+
+    #[test]
+    fn harder_presets_start_faster() {
+        let easy = Difficulty::Easy.speed();
+        let normal = Difficulty::Normal.speed();
+        let hard = Difficulty::Hard.speed();
+        assert!(easy.start > normal.start && normal.start > hard.start);
+        assert!(easy.fastest > normal.fastest && normal.fastest > hard.fastest);
+    }
+}
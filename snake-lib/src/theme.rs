@@ -0,0 +1,279 @@
+//! Colours for drawing a game, shared by the front ends.
+//!
+//! A [`Theme`] holds every colour a renderer needs, both while a game runs
+//! and once it has ended. Front ends turn each [`Rgb`] into whatever their
+//! surface takes: a CSS colour on the canvas, an RGB colour in the terminal.
+
+use crate::SnakeId;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+/// Head and body colours for one snake.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SnakeColors {
+    pub head: Rgb,
+    pub body: Rgb,
+}
+
+/// Colours for the board and everything on it except the snakes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Palette {
+    pub board: Rgb,
+    pub border: Rgb,
+    pub walls: Rgb,
+    pub food: Rgb,
+    pub text: Rgb,
+    /// Picks out a new entry in the high-score table.
+    pub highlight: Rgb,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Theme {
+    pub playing: Palette,
+    pub game_over: Palette,
+    /// One pair per player, reused in turn if there are more players.
+    pub players: Vec<SnakeColors>,
+    /// Snakes that have crashed while others play on.
+    pub dead: SnakeColors,
+    /// Snakes that didn't win, once the game is over.
+    pub losers: SnakeColors,
+}
+
+/// The built-in themes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Preset {
+    /// Bright green snakes on a white board.
+    Classic,
+    /// Bright snakes on a black board.
+    Dark,
+    /// Built from the Okabe–Ito palette, so no two things on the board rely
+    /// on telling red from green.
+    ColourBlind,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct UnknownTheme(pub String);
+
+impl Rgb {
+    /// A CSS colour such as `#FF9900`.
+    pub fn hex(&self) -> String {
+        format!("#{:02X}{:02X}{:02X}", self.0, self.1, self.2)
+    }
+}
+
+impl Theme {
+    pub fn palette(&self, game_over: bool) -> &Palette {
+        if game_over {
+            &self.game_over
+        } else {
+            &self.playing
+        }
+    }
+
+    /// A theme built without any player colours draws every snake in the
+    /// classic green.
+    pub fn player(&self, id: SnakeId) -> SnakeColors {
+        match self.players.len() {
+            0 => CLASSIC_GREEN,
+            n => self.players[id % n],
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Theme {
+        Preset::Classic.theme()
+    }
+}
+
+const fn colors(head: Rgb, body: Rgb) -> SnakeColors {
+    SnakeColors { head, body }
+}
+
+const CLASSIC_GREEN: SnakeColors = colors(Rgb(0x66, 0xFF, 0x66), Rgb(0x00, 0xFF, 0x00));
+
+impl Preset {
+    pub const ALL: [Preset; 3] = [Preset::Classic, Preset::Dark, Preset::ColourBlind];
+
+    pub fn theme(&self) -> Theme {
+        match self {
+            Preset::Classic => Theme {
+                playing: Palette {
+                    board: Rgb(0xFF, 0xFF, 0xFF),
+                    border: Rgb(0x00, 0x00, 0x00),
+                    walls: Rgb(0x44, 0x44, 0x44),
+                    food: Rgb(0xFF, 0x99, 0x00),
+                    text: Rgb(0x00, 0x00, 0x00),
+                    highlight: Rgb(0xCC, 0x00, 0x00),
+                },
+                game_over: Palette {
+                    board: Rgb(0xFF, 0xCC, 0xCC),
+                    border: Rgb(0xFF, 0x00, 0x00),
+                    walls: Rgb(0x66, 0x33, 0x33),
+                    food: Rgb(0x99, 0x66, 0x66),
+                    text: Rgb(0x00, 0x00, 0x00),
+                    highlight: Rgb(0xCC, 0x00, 0x00),
+                },
+                players: vec![
+                    CLASSIC_GREEN,
+                    colors(Rgb(0x66, 0xCC, 0xFF), Rgb(0x00, 0x99, 0xFF)),
+                    colors(Rgb(0xFF, 0x66, 0xFF), Rgb(0xCC, 0x00, 0xCC)),
+                    colors(Rgb(0xFF, 0xFF, 0x66), Rgb(0xCC, 0xCC, 0x00)),
+                ],
+                dead: colors(Rgb(0x99, 0x99, 0x99), Rgb(0x66, 0x66, 0x66)),
+                losers: colors(Rgb(0xFF, 0x66, 0x66), Rgb(0xFF, 0x00, 0x00)),
+            },
+            Preset::Dark => Theme {
+                playing: Palette {
+                    board: Rgb(0x00, 0x00, 0x00),
+                    border: Rgb(0xFF, 0xFF, 0xFF),
+                    walls: Rgb(0xC0, 0xC0, 0xC0),
+                    food: Rgb(0xFF, 0x00, 0x00),
+                    text: Rgb(0xFF, 0xFF, 0xFF),
+                    highlight: Rgb(0xFF, 0xFF, 0x00),
+                },
+                game_over: Palette {
+                    board: Rgb(0x00, 0x00, 0x00),
+                    border: Rgb(0xFF, 0x00, 0x00),
+                    walls: Rgb(0x80, 0x00, 0x00),
+                    food: Rgb(0x80, 0x00, 0x00),
+                    text: Rgb(0xFF, 0x00, 0x00),
+                    highlight: Rgb(0xFF, 0xFF, 0x00),
+                },
+                players: vec![
+                    colors(Rgb(0x66, 0xFF, 0x66), Rgb(0x00, 0xAA, 0x00)),
+                    colors(Rgb(0x66, 0xCC, 0xFF), Rgb(0x00, 0x77, 0xCC)),
+                    colors(Rgb(0xFF, 0x66, 0xFF), Rgb(0xAA, 0x00, 0xAA)),
+                    colors(Rgb(0xFF, 0xFF, 0x66), Rgb(0xAA, 0xAA, 0x00)),
+                ],
+                dead: colors(Rgb(0x80, 0x80, 0x80), Rgb(0x40, 0x40, 0x40)),
+                losers: colors(Rgb(0xFF, 0x66, 0x66), Rgb(0x80, 0x00, 0x00)),
+            },
+            // Losing and crashing are shown in greys rather than reds, and
+            // the game-over board changes brightness rather than hue.
+            Preset::ColourBlind => Theme {
+                playing: Palette {
+                    board: Rgb(0xFF, 0xFF, 0xFF),
+                    border: Rgb(0x00, 0x00, 0x00),
+                    walls: Rgb(0x44, 0x44, 0x44),
+                    food: Rgb(0xD5, 0x5E, 0x00),
+                    text: Rgb(0x00, 0x00, 0x00),
+                    highlight: Rgb(0xD5, 0x5E, 0x00),
+                },
+                game_over: Palette {
+                    board: Rgb(0xDD, 0xDD, 0xDD),
+                    border: Rgb(0xD5, 0x5E, 0x00),
+                    walls: Rgb(0x77, 0x77, 0x77),
+                    food: Rgb(0xBB, 0x99, 0x88),
+                    text: Rgb(0x00, 0x00, 0x00),
+                    highlight: Rgb(0xD5, 0x5E, 0x00),
+                },
+                players: vec![
+                    colors(Rgb(0x56, 0xB4, 0xE9), Rgb(0x00, 0x72, 0xB2)),
+                    colors(Rgb(0xF0, 0xE4, 0x42), Rgb(0xE6, 0x9F, 0x00)),
+                    colors(Rgb(0x5C, 0xD6, 0xB4), Rgb(0x00, 0x9E, 0x73)),
+                    colors(Rgb(0xE8, 0xA8, 0xCC), Rgb(0xCC, 0x79, 0xA7)),
+                ],
+                dead: colors(Rgb(0xAA, 0xAA, 0xAA), Rgb(0x88, 0x88, 0x88)),
+                losers: colors(Rgb(0x66, 0x66, 0x66), Rgb(0x33, 0x33, 0x33)),
+            },
+        }
+    }
+}
+
+impl FromStr for Preset {
+    type Err = UnknownTheme;
+
+    fn from_str(s: &str) -> Result<Preset, UnknownTheme> {
+        Preset::ALL
+            .into_iter()
+            .find(|preset| preset.to_string() == s)
+            .ok_or_else(|| UnknownTheme(s.to_string()))
+    }
+}
+
+impl fmt::Display for Preset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Preset::Classic => write!(f, "classic"),
+            Preset::Dark => write!(f, "dark"),
+            Preset::ColourBlind => write!(f, "colour-blind"),
+        }
+    }
+}
+
+impl fmt::Display for UnknownTheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unknown theme {:?}, expected one of: {}",
+            self.0,
+            Preset::ALL.map(|p| p.to_string()).join(", ")
+        )
+    }
+}
+
+impl std::error::Error for UnknownTheme {}
+
+#[cfg(test)]
+mod test {
+    use crate::theme::{Preset, Rgb, Theme, UnknownTheme};
+    use test_case::test_case;
+
+    #[test]
+    fn hex_is_css_colour() {
+        assert_eq!(Rgb(0xFF, 0x99, 0x00).hex(), "#FF9900");
+        assert_eq!(Rgb(0, 10, 255).hex(), "#000AFF");
+    }
+
+    #[test]
+    fn preset_names_round_trip() {
+        for preset in Preset::ALL {
+            assert_eq!(preset.to_string().parse(), Ok(preset));
+        }
+        assert_eq!(
+            "neon".parse::<Preset>(),
+            Err(UnknownTheme("neon".to_string()))
+        );
+    }
+
+    #[test]
+    fn player_colours_are_reused_in_turn() {
+        let theme = Theme::default();
+        assert_eq!(theme.player(1), theme.players[1]);
+        assert_eq!(theme.player(theme.players.len()), theme.players[0]);
+    }
+
+    #[test]
+    fn theme_without_player_colours_still_colours_players() {
+        let theme = Theme {
+            players: Vec::new(),
+            ..Theme::default()
+        };
+        assert_eq!(theme.player(1), Theme::default().players[0]);
+    }
+
+    #[test]
+    fn palette_follows_game_state() {
+        let theme = Preset::Dark.theme();
+        assert_eq!(theme.palette(false), &theme.playing);
+        assert_eq!(theme.palette(true), &theme.game_over);
+    }
+
+    #[test_case(Preset::Classic)]
+    #[test_case(Preset::Dark)]
+    #[test_case(Preset::ColourBlind)]
+    fn snakes_stand_out_from_the_board(preset: Preset) {
+        let theme = preset.theme();
+        let mut seen = vec![theme.playing.board, theme.playing.walls, theme.playing.food];
+        for colors in &theme.players {
+            for color in [colors.head, colors.body] {
+                assert!(!seen.contains(&color), "{} reuses {}", preset, color.hex());
+                seen.push(color);
+            }
+        }
+    }
+}
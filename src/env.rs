@@ -0,0 +1,71 @@
+use snake::{
+    env::{Action, Encoding, Env, Rewards},
+    GameConfig,
+};
+use wasm_bindgen::prelude::*;
+
+/// The training environment for agents written in JavaScript.
+#[wasm_bindgen]
+pub struct SnakeEnv {
+    env: Env,
+}
+
+#[wasm_bindgen(getter_with_clone)]
+pub struct Transition {
+    pub observation: Vec<f32>,
+    pub reward: f32,
+    pub done: bool,
+}
+
+#[wasm_bindgen]
+impl SnakeEnv {
+    /// `encodings` is a comma-separated list of `grid`, `rays` and `food`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(width: u16, height: u16, encodings: &str) -> Result<SnakeEnv, JsValue> {
+        let encodings = encodings
+            .split(',')
+            .map(|e| e.trim().parse::<Encoding>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        let env = Env::new(GameConfig::new(width, height))
+            .map_err(|e| JsValue::from_str(&e.to_string()))?
+            .encodings(encodings);
+        Ok(SnakeEnv { env })
+    }
+
+    pub fn set_rewards(&mut self, food: f32, death: f32, step: f32, closer: f32) {
+        self.env.rewards = Rewards {
+            food,
+            death,
+            step,
+            closer,
+        };
+    }
+
+    pub fn set_max_idle(&mut self, max_idle: u32) {
+        self.env.max_idle = max_idle;
+    }
+
+    pub fn observation_size(&self) -> usize {
+        self.env.observation_size()
+    }
+
+    /// Takes a `u32` seed so JavaScript can pass a plain number rather than
+    /// a `BigInt`.
+    pub fn reset(&mut self, seed: u32) -> Vec<f32> {
+        self.env.reset(seed.into())
+    }
+
+    /// `action` is 0 to carry on, 1 to turn left or 2 to turn right.
+    pub fn step(&mut self, action: u8) -> Result<Transition, JsValue> {
+        let action = *Action::ALL
+            .get(usize::from(action))
+            .ok_or_else(|| JsValue::from_str(&format!("unknown action {}", action)))?;
+        let (observation, reward, done) = self.env.step(action);
+        Ok(Transition {
+            observation,
+            reward,
+            done,
+        })
+    }
+}
@@ -1,3 +1,4 @@
 mod smiley;
 mod utils;
-mod snake;
\ No newline at end of file
+mod snake;
+mod env;
\ No newline at end of file
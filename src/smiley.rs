@@ -109,12 +109,10 @@ impl Smiley {
 
                 context.begin_path();
 
-                let color: JsValue = vec!["red", "orange", "green", "blue", "yellow", "purple"]
+                let color = ["red", "orange", "green", "blue", "yellow", "purple"]
                     .choose(&mut rand::thread_rng())
-                    .unwrap()
-                    .to_string()
-                    .into();
-                context.set_stroke_style(&color);
+                    .unwrap();
+                context.set_stroke_style_str(color);
 
                 // Draw the outer circle.
                 context
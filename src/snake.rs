@@ -1,9 +1,21 @@
-use snake::{game_step, Coordinate, Game, GameDisplay, GameInput, SnakeChange, Turn};
+use snake::{
+    ai::{Autopilot, Strategy},
+    clock::Clock,
+    game_step,
+    highscores::{self, HighScoreStore, HighScores},
+    level::Level,
+    replay::{Recorder, Replay, ReplayInput},
+    segment::Segment,
+    theme::{Palette, Preset, SnakeColors, Theme},
+    ConfigError, Coordinate, Difficulty, Direction, Game, GameConfig, GameDisplay, GameEvent,
+    GameInput, GameOverReason, Input, SnakeId, Speed, Walls,
+};
 use web_sys::CanvasRenderingContext2d;
 
-use std::cell::RefCell;
-use std::collections::VecDeque;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashSet, VecDeque};
 use std::rc::Rc;
+use std::time::Duration;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 
@@ -11,83 +23,593 @@ use crate::utils::set_panic_hook;
 
 #[wasm_bindgen]
 pub struct Snake {
-    game: Rc<RefCell<Game>>,
-    counter: Rc<RefCell<u32>>,
-    input: Rc<WebInput>,
+    state: Rc<State>,
+    on_keydown: Closure<dyn FnMut(web_sys::KeyboardEvent)>,
+    on_resize: Closure<dyn FnMut()>,
+    on_hide: Closure<dyn FnMut()>,
 }
 
+/// How many ticks apart a running game is saved. It is also saved when it
+/// pauses or the page is hidden, so this only bounds what a crash loses.
+const SAVE_EVERY: u32 = 100;
+
+/// How many ticks Backspace or the rewind button takes back.
+const REWIND_TICKS: usize = 20;
+
+/// Gap around the board and around text, in CSS pixels.
+const MARGIN: f64 = 5.0;
+
+/// Height of a line of 14px text, in CSS pixels.
+const LINE_HEIGHT: f64 = 18.0;
+
+/// Sprite sheets have a row of square tiles for each player, reused in turn
+/// if there are more players. Each row holds heads facing north, east, south
+/// and west; straight pieces joining north to south and east to west; corners
+/// joining north and east, north and west, south and east, south and west;
+/// then tails joining the body to the north, east, south and west.
+const SPRITE_COLUMNS: f64 = 14.0;
+
+/// One cell's step in `direction`, as `(x, y)` with y pointing down.
+fn unit(direction: Direction) -> (f64, f64) {
+    match direction {
+        Direction::North => (0.0, -1.0),
+        Direction::South => (0.0, 1.0),
+        Direction::East => (1.0, 0.0),
+        Direction::West => (-1.0, 0.0),
+    }
+}
+
+/// Which tile along a sprite sheet's row draws `segment`.
+fn sprite_column(segment: &Segment) -> f64 {
+    let joins = |side| segment.sides().contains(&side);
+    match segment {
+        Segment::Head(Direction::North) => 0.0,
+        Segment::Head(Direction::East) => 1.0,
+        Segment::Head(Direction::South) => 2.0,
+        Segment::Head(Direction::West) => 3.0,
+        _ => match (
+            joins(Direction::North),
+            joins(Direction::South),
+            joins(Direction::East),
+            joins(Direction::West),
+        ) {
+            (true, true, _, _) => 4.0,
+            (_, _, true, true) => 5.0,
+            (true, _, true, _) => 6.0,
+            (true, _, _, true) => 7.0,
+            (_, true, true, _) => 8.0,
+            (_, true, _, true) => 9.0,
+            (true, _, _, _) => 10.0,
+            (_, _, true, _) => 11.0,
+            (_, true, _, _) => 12.0,
+            _ => 13.0,
+        },
+    }
+}
+
+/// The page sets the canvas's size on screen with CSS; the display keeps its
+/// drawing buffer at that size in device pixels so nothing is blurred.
 struct CanvasDisplay {
+    canvas: web_sys::HtmlCanvasElement,
     context: Rc<CanvasRenderingContext2d>,
-    width: u32,
-    height: u32,
+    /// Size of the drawing buffer in device pixels.
+    width: Cell<u32>,
+    height: Cell<u32>,
+    /// Device pixels per CSS pixel.
+    scale: Cell<f64>,
+    theme: RefCell<Theme>,
+    /// Drawn from in place of rounded shapes when set.
+    sprites: RefCell<Option<web_sys::HtmlImageElement>>,
+    /// Snakes that moved on the last tick, with the cell each one left.
+    motion: RefCell<Vec<(SnakeId, Option<Coordinate>)>>,
+}
+
+/// Where the board sits on the canvas, in device pixels. Cells are square and
+/// a whole number of pixels across where there is room, and the board is
+/// centred with any space left over around it.
+struct Layout {
+    cell: f64,
+    left: f64,
+    top: f64,
+    width: f64,
+    height: f64,
+    scale: f64,
+}
+
+impl Layout {
+    /// Top-left corner of a cell.
+    fn corner(&self, Coordinate { x, y }: &Coordinate) -> (f64, f64) {
+        (
+            self.left + f64::from(*x) * self.cell,
+            self.top + f64::from(*y) * self.cell,
+        )
+    }
+}
+
+/// A canvas the page doesn't size shows at its drawing buffer's size, so
+/// `fit` scaling the buffer would grow it on every resize. Such a canvas is
+/// held at the size it shows now; one sized by the page is left alone.
+fn pin_unstyled(canvas: &web_sys::HtmlCanvasElement) {
+    let (width, height) = (canvas.width(), canvas.height());
+    // One shown at exactly its buffer's size is pinned too, which changes
+    // nothing.
+    let unstyled = i64::from(canvas.client_width()) == i64::from(width)
+        && i64::from(canvas.client_height()) == i64::from(height);
+    if unstyled {
+        let style = canvas.style();
+        style
+            .set_property("width", &format!("{}px", width))
+            .expect("failed setting canvas width");
+        style
+            .set_property("height", &format!("{}px", height))
+            .expect("failed setting canvas height");
+    }
 }
 
 impl CanvasDisplay {
-    fn new(context: CanvasRenderingContext2d, width: u32, height: u32) -> CanvasDisplay {
+    fn new(canvas: web_sys::HtmlCanvasElement, context: CanvasRenderingContext2d) -> CanvasDisplay {
+        pin_unstyled(&canvas);
         CanvasDisplay {
+            width: Cell::new(canvas.width()),
+            height: Cell::new(canvas.height()),
+            scale: Cell::new(1.0),
+            theme: RefCell::new(Theme::default()),
+            sprites: RefCell::new(None),
+            motion: RefCell::new(Vec::new()),
+            canvas,
             context: Rc::new(context),
+        }
+    }
+
+    /// Resizes the drawing buffer to match the canvas on screen at the
+    /// current `devicePixelRatio`, returning whether it changed. Resizing
+    /// clears the canvas, so the caller should redraw.
+    fn fit(&self) -> bool {
+        let scale = web_sys::window().unwrap().device_pixel_ratio();
+        let (css_width, css_height) = (self.canvas.client_width(), self.canvas.client_height());
+        // Not laid out yet, or hidden.
+        if css_width <= 0 || css_height <= 0 {
+            return false;
+        }
+        let width = (f64::from(css_width) * scale).round() as u32;
+        let height = (f64::from(css_height) * scale).round() as u32;
+        if (width, height, scale) == (self.width.get(), self.height.get(), self.scale.get()) {
+            return false;
+        }
+        self.canvas.set_width(width);
+        self.canvas.set_height(height);
+        self.width.set(width);
+        self.height.set(height);
+        self.scale.set(scale);
+        true
+    }
+
+    fn layout(&self, game: &Game) -> Layout {
+        let scale = self.scale.get();
+        let (canvas_width, canvas_height) =
+            (f64::from(self.width.get()), f64::from(self.height.get()));
+        let margin = (MARGIN * scale).round();
+        let fit = ((canvas_width - 2.0 * margin) / f64::from(game.width))
+            .min((canvas_height - 2.0 * margin) / f64::from(game.height))
+            .max(0.0);
+        // A board with more cells than there are pixels is drawn smaller
+        // rather than spilling off the canvas.
+        let cell = if fit >= 1.0 { fit.floor() } else { fit };
+        let width = cell * f64::from(game.width);
+        let height = cell * f64::from(game.height);
+        Layout {
+            cell,
+            left: ((canvas_width - width) / 2.0).floor(),
+            top: ((canvas_height - height) / 2.0).floor(),
             width,
             height,
+            scale,
         }
     }
 
-    fn draw(&self, game: &Game) {
-        let cell_w: f64 = f64::from(self.width - 10) / f64::from(game.width);
-        let cell_h: f64 = f64::from(self.height - 10) / f64::from(game.height);
+    /// Sets a font of `size` CSS pixels.
+    fn set_font(&self, size: f64) {
+        self.context
+            .set_font(&format!("{}px sans-serif", size * self.scale.get()));
+    }
+
+    fn fill_cell(&self, layout: &Layout, c: &Coordinate) {
+        let (left, top) = layout.corner(c);
+        self.context.fill_rect(left, top, layout.cell, layout.cell);
+    }
+
+    /// The playing colours, or the game-over ones once it has ended.
+    fn palette(&self, game: &Game) -> Palette {
+        self.theme.borrow().palette(game.game_over).clone()
+    }
 
-        if game.game_over {
-            self.context.set_fill_style(&"#FFCCCC".into());
+    /// Head and body colours for a snake, dimmed once it is out.
+    fn snake_colors(theme: &Theme, game: &Game, snake: &snake::Snake) -> SnakeColors {
+        if game.game_over && game.winner != Some(snake.id) {
+            theme.losers
+        } else if !snake.alive {
+            theme.dead
         } else {
-            self.context.set_fill_style(&"#FFFFFF".into());
+            theme.player(snake.id)
         }
-        self.context.fill_rect(
-            5.0,
-            5.0,
-            (self.width - 10).into(),
-            (self.height - 10).into(),
-        );
+    }
 
-        if game.game_over {
-            self.context.set_fill_style(&"#FF6666".into());
-        } else {
-            self.context.set_fill_style(&"#66FF66".into());
+    /// Repaints the whole board. Used when a game starts or ends; while it
+    /// runs `update` only repaints the cells that changed.
+    fn draw(&self, game: &Game) {
+        self.motion.borrow_mut().clear();
+        let layout = self.layout(game);
+        let theme = self.theme.borrow();
+        let palette = theme.palette(game.game_over);
+        self.context
+            .clear_rect(0.0, 0.0, self.width.get().into(), self.height.get().into());
+        self.context.set_fill_style_str(&palette.board.hex());
+        self.context
+            .fill_rect(layout.left, layout.top, layout.width, layout.height);
+
+        self.context.set_fill_style_str(&palette.walls.hex());
+        for c in game.obstacles.iter() {
+            self.fill_cell(&layout, c);
         }
 
-        if let Some(Coordinate { x, y }) = game.snake.front() {
-            self.context.fill_rect(
-                5.0 + f64::from(*x) * cell_w,
-                5.0 + f64::from(*y) * cell_h,
-                cell_w,
-                cell_h,
-            );
+        self.context.set_fill_style_str(&palette.food.hex());
+        for c in game.food.iter() {
+            self.fill_cell(&layout, c);
         }
 
-        if game.game_over {
-            self.context.set_fill_style(&"#FF0000".into());
+        for snake in game.snakes.iter() {
+            for (segment, c) in game.segments(snake.id).iter().zip(&snake.body) {
+                self.draw_segment(&layout, game, snake, segment, layout.corner(c));
+            }
+        }
+
+        self.draw_border(game);
+        self.draw_score(game);
+        if let Some(reason) = &game.game_over_reason {
+            self.draw_game_over_reason(game, reason);
+        }
+    }
+
+    /// Repaints one cell of a running game with whatever is in it now.
+    fn draw_cell(&self, game: &Game, c: &Coordinate) {
+        let layout = self.layout(game);
+        let palette = self.palette(game);
+        let snake = game
+            .snakes
+            .iter()
+            .find_map(|s| s.body.iter().position(|b| b == c).map(|i| (s, i)));
+        let color = match snake {
+            _ if game.obstacles.contains(c) => palette.walls,
+            None if game.food.contains(c) => palette.food,
+            _ => palette.board,
+        };
+        self.context.set_fill_style_str(&color.hex());
+        self.fill_cell(&layout, c);
+        match snake {
+            Some((snake, i)) if !game.obstacles.contains(c) => {
+                let segment = game.segments(snake.id)[i];
+                self.draw_segment(&layout, game, snake, &segment, layout.corner(c));
+            }
+            _ => {}
+        }
+    }
+
+    /// Draws one piece of a snake over the cell whose corner is at `(left,
+    /// top)`: a tile from the sprite sheet if there is one, otherwise a
+    /// rounded shape that joins up with its neighbours.
+    fn draw_segment(
+        &self,
+        layout: &Layout,
+        game: &Game,
+        snake: &snake::Snake,
+        segment: &Segment,
+        (left, top): (f64, f64),
+    ) {
+        let out = !snake.alive || (game.game_over && game.winner != Some(snake.id));
+
+        // A sheet that is still loading, or failed to, has nothing to draw
+        // from, so those snakes get the rounded shapes instead.
+        let sprites = self.sprites.borrow();
+        let sheet = sprites
+            .as_ref()
+            .filter(|sheet| sheet.complete() && sheet.natural_width() > 0);
+        if let Some(sheet) = sheet {
+            let tile = f64::from(sheet.natural_width()) / SPRITE_COLUMNS;
+            let rows = (f64::from(sheet.natural_height()) / tile).floor().max(1.0);
+            let row = (snake.id as f64 % rows).floor();
+            // Snakes that are out are faded rather than recoloured.
+            if out {
+                self.context.set_global_alpha(0.5);
+            }
+            let drawn = self
+                .context
+                .draw_image_with_html_image_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
+                    sheet,
+                    sprite_column(segment) * tile,
+                    row * tile,
+                    tile,
+                    tile,
+                    left,
+                    top,
+                    layout.cell,
+                    layout.cell,
+                );
+            self.context.set_global_alpha(1.0);
+            match drawn {
+                Ok(()) => return,
+                Err(e) => web_sys::console::error_1(&e),
+            }
+        }
+
+        let theme = self.theme.borrow();
+        let colors = CanvasDisplay::snake_colors(&theme, game, snake);
+        let half = layout.cell / 2.0;
+        let radius = layout.cell * 0.4;
+        let (cx, cy) = (left + half, top + half);
+        let color = match segment {
+            Segment::Head(_) => colors.head,
+            _ => colors.body,
+        };
+        self.context.set_fill_style_str(&color.hex());
+        self.context.begin_path();
+        self.context
+            .arc(cx, cy, radius, 0.0, std::f64::consts::TAU)
+            .expect("failed drawing snake");
+        self.context.fill();
+        // A band out to each side the segment joins, so the body is one
+        // continuous shape with rounded ends and corners.
+        let sides = if snake.len() > 1 {
+            segment.sides()
         } else {
-            self.context.set_fill_style(&"#00FF00".into());
+            Vec::new()
+        };
+        for side in sides {
+            match side {
+                Direction::North => self.context.fill_rect(cx - radius, top, 2.0 * radius, half),
+                Direction::South => self.context.fill_rect(cx - radius, cy, 2.0 * radius, half),
+                Direction::East => self.context.fill_rect(cx, cy - radius, half, 2.0 * radius),
+                Direction::West => self
+                    .context
+                    .fill_rect(left, cy - radius, half, 2.0 * radius),
+            }
+        }
+
+        if let Segment::Head(facing) = segment {
+            let (dx, dy) = unit(*facing);
+            self.context
+                .set_fill_style_str(&theme.palette(game.game_over).board.hex());
+            for side in [-1.0, 1.0] {
+                // Eyes sit forward of the centre, either side of the line
+                // the head is facing along.
+                let ex = cx + layout.cell * (0.15 * dx - 0.2 * side * dy);
+                let ey = cy + layout.cell * (0.15 * dy + 0.2 * side * dx);
+                self.context.begin_path();
+                self.context
+                    .arc(ex, ey, layout.cell * 0.08, 0.0, std::f64::consts::TAU)
+                    .expect("failed drawing snake");
+                self.context.fill();
+            }
         }
+    }
+
+    /// Slides the snakes that moved on the last tick between their old and
+    /// new cells, `progress` being how far the clock is through the tick.
+    /// The board is painted as it stands first, then the head is drawn
+    /// short of its cell and the tail short of leaving its old one.
+    fn animate(&self, game: &Game, progress: f64) {
+        let motion = self.motion.borrow();
+        if motion.is_empty() || game.game_over {
+            return;
+        }
+        let layout = self.layout(game);
+        let moving: Vec<(&snake::Snake, Option<&Coordinate>)> = motion
+            .iter()
+            .map(|(id, vacated)| (&game.snakes[*id], vacated.as_ref()))
+            .filter(|(snake, _)| snake.alive && !snake.is_empty())
+            .collect();
+
+        let mut cells: HashSet<Coordinate> = HashSet::new();
+        for (snake, vacated) in &moving {
+            cells.extend(snake.body.iter().take(2).cloned());
+            cells.extend(snake.body.back().cloned());
+            cells.extend(vacated.cloned());
+        }
+        // As in `update`, the score goes back over any rows repainted under it.
+        let rows = self.score_rows(game);
+        let over_score = cells.iter().any(|c| rows.contains(&c.y));
+        if over_score {
+            for y in rows {
+                cells.extend((0..i32::from(game.width)).map(|x| Coordinate { x, y }));
+            }
+        }
+        for c in &cells {
+            self.draw_cell(game, c);
+        }
+
+        // Nothing slides out past the edge of the board.
+        self.context.save();
+        self.context.begin_path();
+        self.context
+            .rect(layout.left, layout.top, layout.width, layout.height);
+        self.context.clip();
 
-        for Coordinate { x, y } in game.snake.iter().skip(1) {
-            self.context.fill_rect(
-                5.0 + f64::from(*x) * cell_w,
-                5.0 + f64::from(*y) * cell_h,
-                cell_w,
-                cell_h,
+        for (snake, vacated) in &moving {
+            let (vacated, tail) = match (vacated, snake.body.back()) {
+                (Some(vacated), Some(tail)) => (vacated, tail),
+                _ => continue,
+            };
+            let direction = match game.direction_between(vacated, tail) {
+                Some(direction) => direction,
+                None => continue,
+            };
+            // The new tail still reaches back into the cell being left...
+            if let Some(Segment::Tail(to)) = game.segments(snake.id).last() {
+                let joined = if *to == direction {
+                    Segment::Straight(direction)
+                } else {
+                    Segment::Corner {
+                        from: direction,
+                        to: *to,
+                    }
+                };
+                self.draw_segment(&layout, game, snake, &joined, layout.corner(tail));
+            }
+            // ...where the tip of the tail is on its way out.
+            let (x, y) = layout.corner(vacated);
+            let (dx, dy) = unit(direction);
+            let step = progress * layout.cell;
+            self.context.save();
+            self.context.begin_path();
+            self.context.rect(x, y, layout.cell, layout.cell);
+            self.context.clip();
+            self.draw_segment(
+                &layout,
+                game,
+                snake,
+                &Segment::Tail(direction),
+                (x + dx * step, y + dy * step),
             );
+            self.context.restore();
+        }
+
+        let board = self.palette(game).board.hex();
+        for (snake, _) in &moving {
+            let head = &snake.body[0];
+            let segment = game.segments(snake.id)[0];
+            if let Segment::Head(facing) = segment {
+                self.context.set_fill_style_str(&board);
+                self.fill_cell(&layout, head);
+                let (x, y) = layout.corner(head);
+                let (dx, dy) = unit(facing);
+                let behind = (1.0 - progress) * layout.cell;
+                self.draw_segment(
+                    &layout,
+                    game,
+                    snake,
+                    &segment,
+                    (x - dx * behind, y - dy * behind),
+                );
+            }
         }
 
-        if game.game_over {
-            self.context.set_stroke_style(&"#FF0000".into());
-        } else{
-            self.context.set_stroke_style(&"#000000".into());
+        self.context.restore();
+        self.draw_border(game);
+        if over_score {
+            self.draw_score(game);
+        }
+    }
+
+    fn draw_border(&self, game: &Game) {
+        let layout = self.layout(game);
+        let palette = self.palette(game);
+        self.context.set_stroke_style_str(&palette.border.hex());
+        // A dashed border marks a board that wraps around.
+        let dash = match game.config.walls {
+            Walls::Solid => js_sys::Array::new(),
+            Walls::Wrap => {
+                let length = 4.0 * layout.scale;
+                js_sys::Array::of2(&length.into(), &length.into())
+            }
+        };
+        self.context
+            .set_line_dash(&dash)
+            .expect("failed setting line dash");
+        self.context.set_line_width(layout.scale);
+        self.context
+            .stroke_rect(layout.left, layout.top, layout.width, layout.height);
+    }
+
+    /// Rows of cells the score is written over.
+    fn score_rows(&self, game: &Game) -> std::ops::RangeInclusive<i32> {
+        let layout = self.layout(game);
+        let bottom = (MARGIN + LINE_HEIGHT) * layout.scale;
+        0..=((bottom / layout.cell) as i32).min(i32::from(game.height) - 1)
+    }
+
+    fn draw_score(&self, game: &Game) {
+        let text = if game.snakes.len() == 1 {
+            format!(
+                "Score: {}  Level: {}",
+                game.snakes[0].score().total(),
+                game.level()
+            )
+        } else {
+            game.snakes
+                .iter()
+                .map(|s| format!("P{}: {}", s.id + 1, s.score().total()))
+                .chain([format!("Level: {}", game.level())])
+                .collect::<Vec<String>>()
+                .join("  ")
+        };
+        let layout = self.layout(game);
+        self.set_font(14.0);
+        self.context.set_text_baseline("top");
+        self.context
+            .set_fill_style_str(&self.palette(game).text.hex());
+        self.context
+            .fill_text(
+                &text,
+                layout.left + MARGIN * layout.scale,
+                layout.top + MARGIN * layout.scale,
+            )
+            .expect("failed drawing score");
+    }
+
+    fn draw_game_over_reason(&self, game: &Game, reason: &GameOverReason) {
+        let layout = self.layout(game);
+        self.set_font(14.0);
+        self.context.set_text_baseline("bottom");
+        self.context
+            .set_fill_style_str(&self.palette(game).text.hex());
+        self.context
+            .fill_text(
+                &format!("Game over: {}", reason),
+                layout.left + MARGIN * layout.scale,
+                layout.top + layout.height - MARGIN * layout.scale,
+            )
+            .expect("failed drawing game over reason");
+    }
+
+    fn draw_paused(&self, game: &Game) {
+        self.draw(game);
+        let layout = self.layout(game);
+        self.set_font(24.0);
+        self.context.set_text_align("center");
+        self.context.set_text_baseline("middle");
+        self.context
+            .set_fill_style_str(&self.palette(game).text.hex());
+        self.context
+            .fill_text(
+                "Paused",
+                layout.left + layout.width / 2.0,
+                layout.top + layout.height / 2.0,
+            )
+            .expect("failed drawing pause message");
+        self.context.set_text_align("start");
+    }
+
+    fn draw_high_scores(&self, game: &Game, scores: &HighScores, rank: Option<usize>) {
+        let layout = self.layout(game);
+        let left = layout.left + MARGIN * layout.scale;
+        let line = |i: usize| layout.top + (MARGIN + LINE_HEIGHT * (i + 1) as f64) * layout.scale;
+        let palette = self.palette(game);
+        self.set_font(14.0);
+        self.context.set_text_baseline("top");
+        self.context.set_fill_style_str(&palette.text.hex());
+        self.context
+            .fill_text("High scores", left, line(0))
+            .expect("failed drawing high scores");
+        for (i, entry) in scores.top(highscores::SHOWN).iter().enumerate() {
+            if Some(i) == rank {
+                self.context.set_fill_style_str(&palette.highlight.hex());
+            } else {
+                self.context.set_fill_style_str(&palette.text.hex());
+            }
+            let text = format!("{}. {}  {}", i + 1, entry.name, entry.score);
+            self.context
+                .fill_text(&text, left, line(i + 1))
+                .expect("failed drawing high scores");
         }
-        self.context.stroke_rect(
-            5.0,
-            5.0,
-            (self.width - 10).into(),
-            (self.height - 10).into(),
-        );
     }
 }
 
@@ -100,76 +622,713 @@ impl GameDisplay for CanvasDisplay {
         self.draw(game)
     }
 
-    fn update(&self, game: &Game, _change: &SnakeChange) {
-        self.draw(game)
+    fn update(&self, game: &Game, events: &[GameEvent]) {
+        *self.motion.borrow_mut() = events
+            .iter()
+            .filter_map(|event| match event {
+                GameEvent::Moved { id, tail, .. } => Some((*id, tail.clone())),
+                _ => None,
+            })
+            .collect();
+
+        // Cells are repainted from the board as it is now, so order doesn't
+        // matter and a cell changed twice is painted once.
+        let mut dirty: HashSet<Coordinate> = HashSet::new();
+        let mut redraw_score = false;
+        for event in events {
+            match event {
+                GameEvent::Moved { id, head, tail } => {
+                    dirty.insert(head.clone());
+                    dirty.extend(tail.clone());
+                    // The old head is body now, and whatever was second to
+                    // last is the tail.
+                    let body = &game.snakes[*id].body;
+                    dirty.extend(body.get(1).cloned());
+                    dirty.extend(body.back().cloned());
+                }
+                GameEvent::AteFood { spawned, .. } => dirty.extend(spawned.clone()),
+                GameEvent::HitWall { id, tail, .. }
+                | GameEvent::HitSelf { id, tail, .. }
+                | GameEvent::HitSnake { id, tail, .. } => {
+                    dirty.extend(tail.clone());
+                    dirty.extend(game.snakes[*id].body.iter().cloned())
+                }
+                GameEvent::Scored { .. } | GameEvent::LevelUp { .. } => redraw_score = true,
+                _ => {}
+            }
+        }
+
+        // The score is written over the board, so repaint the rows under it
+        // whenever either changes.
+        let rows = self.score_rows(game);
+        if redraw_score || dirty.iter().any(|c| rows.contains(&c.y)) {
+            for y in rows {
+                dirty.extend((0..i32::from(game.width)).map(|x| Coordinate { x, y }));
+            }
+            redraw_score = true;
+        }
+        for c in &dirty {
+            self.draw_cell(game, c);
+        }
+        self.draw_border(game);
+        if redraw_score {
+            self.draw_score(game);
+        }
     }
 }
 
 struct WebInput {
-    keypresses: RefCell<VecDeque<Turn>>,
+    keypresses: RefCell<Vec<VecDeque<Input>>>,
 }
 
 impl WebInput {
-    fn new() -> WebInput {
+    fn new(players: usize) -> WebInput {
         WebInput {
-            keypresses: RefCell::new(VecDeque::new()),
+            keypresses: RefCell::new(vec![VecDeque::new(); players]),
         }
     }
 
-    fn push_keypress(&self, turn: Turn) {
-        self.keypresses.borrow_mut().push_back(turn);
+    fn players(&self) -> usize {
+        self.keypresses.borrow().len()
+    }
+
+    /// Drops queued keys and resizes for a new game's players.
+    fn reset(&self, players: usize) {
+        *self.keypresses.borrow_mut() = vec![VecDeque::new(); players];
+    }
+
+    fn push_keypress(&self, id: SnakeId, input: Input) {
+        self.keypresses.borrow_mut()[id].push_back(input);
     }
 }
 
 impl GameInput for WebInput {
-    fn poll(&self) -> Option<Turn> {
-        self.keypresses.borrow_mut().pop_front()
+    fn poll(&self, id: SnakeId) -> Option<Input> {
+        self.keypresses.borrow_mut().get_mut(id)?.pop_front()
     }
 }
 
-#[wasm_bindgen]
-impl Snake {
-    pub fn new(id: &str, width: u16, height: u16) -> Snake {
-        set_panic_hook();
+/// High scores kept as JSON in the browser's `localStorage`.
+struct LocalStore {
+    key: &'static str,
+}
+
+impl LocalStore {
+    fn storage() -> Result<web_sys::Storage, JsValue> {
+        web_sys::window()
+            .unwrap()
+            .local_storage()?
+            .ok_or_else(|| JsValue::from_str("localStorage is unavailable"))
+    }
+}
+
+impl HighScoreStore for LocalStore {
+    type Error = JsValue;
 
-        let result = Snake {
-            game: Rc::new(RefCell::new(Game::new(&width, &height))),
-            counter: Rc::new(RefCell::new(0)),
-            input: Rc::new(WebInput::new()),
+    fn load(&self) -> Result<HighScores, JsValue> {
+        match LocalStore::storage()?.get_item(self.key)? {
+            Some(text) => {
+                serde_json::from_str(&text).map_err(|e| JsValue::from_str(&e.to_string()))
+            }
+            None => Ok(HighScores::default()),
+        }
+    }
+
+    fn save(&self, scores: &HighScores) -> Result<(), JsValue> {
+        let text = serde_json::to_string(scores).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        LocalStore::storage()?.set_item(self.key, &text)
+    }
+}
+
+const HIGH_SCORES: LocalStore = LocalStore {
+    key: "snake-high-scores",
+};
+
+/// `localStorage` keys for the game in progress and its recording.
+const SAVED_GAME: &str = "snake-game";
+const SAVED_REPLAY: &str = "snake-replay";
+
+/// Everything the animation loop and key handler share with a `Snake`.
+struct State {
+    game: RefCell<Game>,
+    input: Recorder<WebInput>,
+    /// Set while a replay is playing, in place of the keyboard.
+    playback: RefCell<Option<ReplayInput>>,
+    /// Set in demo mode, in place of the keyboard.
+    autopilot: RefCell<Option<Autopilot>>,
+    display: CanvasDisplay,
+    player: RefCell<String>,
+    /// The high scores from before this game's entry went in, so rewinding
+    /// after the crash takes the entry back out.
+    unrecorded: RefCell<Option<HighScores>>,
+    paused: Cell<bool>,
+    clock: RefCell<Clock>,
+    /// The pending animation frame request, if the loop is running.
+    frame: Cell<Option<i32>>,
+    #[allow(clippy::type_complexity)]
+    animate: RefCell<Option<Closure<dyn FnMut(f64)>>>,
+    /// A sprite sheet waiting to load before it is drawn from.
+    loading_sprites: RefCell<Option<web_sys::HtmlImageElement>>,
+    on_sprites_load: RefCell<Option<Closure<dyn FnMut()>>>,
+}
+
+impl State {
+    fn is_running(&self) -> bool {
+        let replay_over = matches!(&*self.playback.borrow(), Some(replay) if replay.finished());
+        !self.paused.get() && !self.game.borrow().game_over && !replay_over
+    }
+
+    /// Requests the next animation frame unless one is already pending.
+    fn schedule(&self) {
+        if self.frame.get().is_some() || !self.is_running() {
+            return;
+        }
+        let handle = web_sys::window()
+            .unwrap()
+            .request_animation_frame(
+                self.animate
+                    .borrow()
+                    .as_ref()
+                    .unwrap()
+                    .as_ref()
+                    .unchecked_ref(),
+            )
+            .expect("failed requesting animation frame");
+        self.frame.set(Some(handle));
+    }
+
+    /// `timestamp` is the `performance.now()` time passed to the frame
+    /// callback, in milliseconds.
+    fn tick(&self, timestamp: f64) {
+        self.frame.set(None);
+        if !self.is_running() {
+            return;
+        }
+
+        let now = Duration::from_secs_f64(timestamp.max(0.0) / 1000.0);
+        let ticks = self.clock.borrow_mut().advance(now);
+        let before = self.game.borrow().ticks;
+        for _ in 0..ticks {
+            {
+                let mut game = self.game.borrow_mut();
+                let playback = self.playback.borrow();
+                let autopilot = self.autopilot.borrow();
+                match (&*playback, &*autopilot) {
+                    (Some(replay), _) => game_step(&mut game, &self.display, replay),
+                    (None, Some(autopilot)) => {
+                        autopilot.observe(&game);
+                        game_step(&mut game, &self.display, autopilot)
+                    }
+                    (None, None) => game_step(&mut game, &self.display, &self.input),
+                }
+                if game.game_over && game.snakes.len() == 1 && !self.is_demo() {
+                    *self.unrecorded.borrow_mut() =
+                        record_high_score(&game, &self.player.borrow(), &self.display);
+                }
+            }
+            if !self.is_running() {
+                break;
+            }
+        }
+        // Demo mode plays game after game until someone takes over.
+        if self.autopilot.borrow().is_some() && self.game.borrow().game_over {
+            self.restart(&self.next_config())
+                .expect("failed restarting game");
+        }
+        // Progress is measured against the interval the next tick was
+        // scheduled with, so animate before the level changes it.
+        let progress = self.clock.borrow().progress(now);
+        self.display.animate(&self.game.borrow(), progress);
+        self.clock.borrow_mut().interval = self.game.borrow().tick_interval();
+        // The replay grows every tick, so only save it now and then, and
+        // once more to clear the save when the game ends.
+        let saving = {
+            let game = self.game.borrow();
+            game.game_over || game.ticks / SAVE_EVERY != before / SAVE_EVERY
         };
+        if saving {
+            self.save();
+        }
 
-        result.start(id);
+        self.schedule();
+    }
 
-        result
+    /// Returns whether it paused; a paused or finished game is left alone.
+    fn pause(&self) -> bool {
+        let game = self.game.borrow();
+        if self.paused.get() || game.game_over {
+            return false;
+        }
+        self.paused.set(true);
+        self.cancel();
+        self.display.draw_paused(&game);
+        self.save();
+        true
     }
 
-    fn start(&self, id: &str) {
-        let document = web_sys::window().unwrap().document().unwrap();
-        let canvas = document.get_element_by_id(id).unwrap();
+    /// Keeps the game in `localStorage` so `Snake::load` can pick it up
+    /// after a reload, and clears it once the game is over. Replays and demos
+    /// leave whatever the player last saved alone.
+    fn save(&self) {
+        if self.is_demo() {
+            return;
+        }
+        let result = LocalStore::storage().and_then(|storage| {
+            let game = self.game.borrow();
+            if game.game_over {
+                storage.remove_item(SAVED_GAME)?;
+                return storage.remove_item(SAVED_REPLAY);
+            }
+            let replay = serde_json::to_string(&self.input.replay())
+                .map_err(|e| JsValue::from_str(&e.to_string()))?;
+            let game =
+                serde_json::to_string(&*game).map_err(|e| JsValue::from_str(&e.to_string()))?;
+            storage.set_item(SAVED_GAME, &game)?;
+            storage.set_item(SAVED_REPLAY, &replay)
+        });
+        if let Err(e) = result {
+            web_sys::console::error_1(&e);
+        }
+    }
+
+    fn cancel(&self) {
+        if let Some(handle) = self.frame.take() {
+            web_sys::window()
+                .unwrap()
+                .cancel_animation_frame(handle)
+                .expect("failed cancelling animation frame");
+        }
+    }
 
+    fn resume(&self) {
+        if !self.paused.get() {
+            return;
+        }
+        self.paused.set(false);
+        self.clock.borrow_mut().reset();
+        self.display.draw(&self.game.borrow());
+        self.schedule();
+    }
+
+    fn restart(&self, config: &GameConfig) -> Result<(), ConfigError> {
+        self.start(config, None)
+    }
+
+    /// Starts a new game played by `playback` if given, which is in place
+    /// before the new game is saved so a replay never replaces a save.
+    fn start(&self, config: &GameConfig, playback: Option<ReplayInput>) -> Result<(), ConfigError> {
+        let game = Game::new(config)?;
+        self.input.input().reset(game.snakes.len());
+        self.input.reset(config);
+        *self.playback.borrow_mut() = playback;
+        *self.unrecorded.borrow_mut() = None;
+        self.display.initialize(&game);
+        *self.clock.borrow_mut() = Clock::new(game.tick_interval());
+        *self.game.borrow_mut() = game;
+        self.paused.set(false);
+        if self.autopilot.borrow().is_none() {
+            self.save();
+        }
+        self.schedule();
+        Ok(())
+    }
+
+    /// Takes back the last few ticks, even after a crash, and pauses so the
+    /// player can get ready. Replays and demos can't be rewound. Returns
+    /// whether anything was taken back.
+    fn rewind(&self) -> bool {
+        if self.is_demo() {
+            return false;
+        }
         {
-            let input_ref = self.input.clone();
-
-            let on_keydown: Closure<dyn FnMut(_)> =
-                Closure::wrap(Box::new(move |event: web_sys::KeyboardEvent| {
-                    let key = event.key();
-                    if key == "ArrowLeft" {
-                        input_ref.push_keypress(Turn::Left);
-                        event.prevent_default();
-                    } else if key == "ArrowRight" {
-                        input_ref.push_keypress(Turn::Right);
-                        event.prevent_default();
-                    }
-                }));
+            let mut game = self.game.borrow_mut();
+            if game.rewind(REWIND_TICKS) == 0 {
+                return false;
+            }
+            self.input.rewind(&game);
+            self.input.input().reset(game.snakes.len());
+            // The game carries on, so it ends with a new entry or none.
+            if let Some(scores) = self.unrecorded.take() {
+                if let Err(e) = HIGH_SCORES.save(&scores) {
+                    web_sys::console::error_1(&e);
+                }
+            }
+            self.cancel();
+            self.paused.set(true);
+            self.display.draw_paused(&game);
+        }
+        self.save();
+        true
+    }
 
-            document
-                .add_event_listener_with_callback("keydown", on_keydown.as_ref().unchecked_ref())
-                .expect("failed setting keydown handler");
+    /// Redraws at the canvas's new size, if it has changed.
+    fn resize(&self) {
+        if self.display.fit() {
+            self.redraw();
+        }
+    }
+
+    fn set_theme(&self, theme: Theme) {
+        *self.display.theme.borrow_mut() = theme;
+        self.redraw();
+    }
 
-            on_keydown.forget();
+    /// A sheet that is still loading isn't drawn from until it has, so the
+    /// board never mixes sprites with the shapes standing in for them.
+    fn set_sprites(&self, sheet: Option<web_sys::HtmlImageElement>) {
+        self.stop_loading_sprites();
+        match sheet {
+            Some(sheet) if !sheet.complete() => {
+                sheet
+                    .add_event_listener_with_callback(
+                        "load",
+                        self.on_sprites_load
+                            .borrow()
+                            .as_ref()
+                            .unwrap()
+                            .as_ref()
+                            .unchecked_ref(),
+                    )
+                    .expect("failed setting sprite load handler");
+                *self.loading_sprites.borrow_mut() = Some(sheet);
+                *self.display.sprites.borrow_mut() = None;
+            }
+            sheet => *self.display.sprites.borrow_mut() = sheet,
         }
+        self.redraw();
+    }
+
+    fn sprites_loaded(&self) {
+        if let Some(sheet) = self.stop_loading_sprites() {
+            *self.display.sprites.borrow_mut() = Some(sheet);
+            self.redraw();
+        }
+    }
+
+    /// Stops waiting for a sprite sheet to load, returning it if there was one.
+    fn stop_loading_sprites(&self) -> Option<web_sys::HtmlImageElement> {
+        let sheet = self.loading_sprites.take()?;
+        sheet
+            .remove_event_listener_with_callback(
+                "load",
+                self.on_sprites_load
+                    .borrow()
+                    .as_ref()
+                    .unwrap()
+                    .as_ref()
+                    .unchecked_ref(),
+            )
+            .expect("failed removing sprite load handler");
+        Some(sheet)
+    }
+
+    /// Repaints everything for the state the game is in.
+    fn redraw(&self) {
+        let game = self.game.borrow();
+        if self.paused.get() {
+            self.display.draw_paused(&game);
+            return;
+        }
+        self.display.draw(&game);
+        if game.game_over && game.snakes.len() == 1 && !self.is_demo() {
+            match HIGH_SCORES.load() {
+                Ok(scores) => self.display.draw_high_scores(&game, &scores, None),
+                Err(e) => web_sys::console::error_1(&e),
+            }
+        }
+    }
+
+    /// Checked like any other config, since a restart reuses it.
+    fn set_speed(&self, speed: Speed) -> Result<(), ConfigError> {
+        let mut game = self.game.borrow_mut();
+        game.config.clone().speed(speed).validate()?;
+        game.config.speed = speed;
+        self.clock.borrow_mut().interval = game.tick_interval();
+        Ok(())
+    }
+
+    fn play(&self, replay: &Replay) -> Result<(), ConfigError> {
+        self.start(&replay.config, Some(ReplayInput::new(replay)))
+    }
+
+    /// The current config with a fresh seed, so a restart plays a new game.
+    fn next_config(&self) -> GameConfig {
+        self.game.borrow().config.clone().seed(rand::random())
+    }
+
+    /// Whether the game is being played by anything but the keyboard.
+    fn is_demo(&self) -> bool {
+        self.playback.borrow().is_some() || self.autopilot.borrow().is_some()
+    }
+
+    fn set_autopilot(&self, strategy: Option<Strategy>) {
+        *self.autopilot.borrow_mut() = strategy.map(|s| s.autopilot());
+        self.restart(&self.next_config())
+            .expect("failed restarting game");
+    }
+
+    fn keydown(&self, event: &web_sys::KeyboardEvent) {
+        if typing(event) {
+            return;
+        }
+        // WASD is an alternative to the arrows for a lone player.
+        let wasd = if self.input.input().players() > 1 {
+            1
+        } else {
+            0
+        };
+        let steer = match event.key().as_str() {
+            "ArrowUp" => Some((0, Direction::North)),
+            "ArrowDown" => Some((0, Direction::South)),
+            "ArrowLeft" => Some((0, Direction::West)),
+            "ArrowRight" => Some((0, Direction::East)),
+            "w" | "W" => Some((wasd, Direction::North)),
+            "s" | "S" => Some((wasd, Direction::South)),
+            "a" | "A" => Some((wasd, Direction::West)),
+            "d" | "D" => Some((wasd, Direction::East)),
+            _ => None,
+        };
+        let handled = match (steer, event.key().as_str()) {
+            (Some((id, d)), _) => {
+                if self.autopilot.borrow().is_some() {
+                    // Steering during the demo starts a game for the player.
+                    self.set_autopilot(None);
+                }
+                self.input.input().push_keypress(id, Input::Direction(d));
+                true
+            }
+            (None, " ") if self.paused.get() => {
+                self.resume();
+                true
+            }
+            (None, " " | "Escape") => self.pause(),
+            (None, "Backspace") => self.rewind(),
+            (None, "r" | "R") => {
+                // The current config was already valid, so this can't fail.
+                self.restart(&self.next_config())
+                    .expect("failed restarting game");
+                true
+            }
+            _ => false,
+        };
+        // Keys the game had no use for keep doing what they do on the page.
+        if handled {
+            event.prevent_default();
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl Snake {
+    pub fn new(id: &str, width: u16, height: u16, wrap: bool) -> Result<Snake, JsValue> {
+        Snake::create(id, GameConfig::new(width, height), wrap)
+    }
+
+    /// Why the game ended, or `undefined` while it is still going.
+    pub fn game_over_reason(&self) -> Option<String> {
+        self.state
+            .game
+            .borrow()
+            .game_over_reason
+            .as_ref()
+            .map(|r| r.to_string())
+    }
+
+    /// Total points for the given player (zero-based).
+    pub fn score(&self, player: usize) -> u32 {
+        self.state
+            .game
+            .borrow()
+            .snakes
+            .get(player)
+            .map_or(0, |s| s.score().total())
+    }
+
+    /// The name recorded against this game's high score.
+    pub fn set_player_name(&self, name: &str) {
+        *self.state.player.borrow_mut() = name.to_string();
+    }
+
+    /// The saved high-score table as JSON.
+    pub fn high_scores() -> Result<String, JsValue> {
+        let scores = HIGH_SCORES.load()?;
+        serde_json::to_string(&scores).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    pub fn pause(&self) {
+        self.state.pause();
+    }
+
+    pub fn resume(&self) {
+        self.state.resume();
+    }
+
+    /// Takes back the last few ticks and pauses; `resume` carries on.
+    pub fn rewind(&self) {
+        self.state.rewind();
+    }
+
+    /// Plays at a constant number of ticks a second.
+    pub fn set_tick_rate(&self, rate: f64) -> Result<(), JsValue> {
+        let clock = Clock::with_rate(rate).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        self.state
+            .set_speed(Speed::constant(clock.interval))
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// Speed presets that ramp up with the score: "easy", "normal" or "hard".
+    pub fn set_difficulty(&self, difficulty: &str) -> Result<(), JsValue> {
+        let difficulty = match difficulty {
+            "easy" => Difficulty::Easy,
+            "normal" => Difficulty::Normal,
+            "hard" => Difficulty::Hard,
+            _ => return Err(JsValue::from_str("difficulty must be easy, normal or hard")),
+        };
+        self.state
+            .set_speed(difficulty.speed())
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// Built-in colours: "classic", "dark" or "colour-blind".
+    pub fn set_theme(&self, theme: &str) -> Result<(), JsValue> {
+        let preset = theme
+            .parse::<Preset>()
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        self.state.set_theme(preset.theme());
+        Ok(())
+    }
+
+    /// Draws the snakes from a sprite sheet image, or with rounded shapes in
+    /// the theme's colours again if `None`. Until the image has loaded the
+    /// shapes stand in for it.
+    pub fn set_sprites(&self, sheet: Option<web_sys::HtmlImageElement>) {
+        self.state.set_sprites(sheet);
+    }
+
+    /// False while paused or once the game is over.
+    pub fn is_running(&self) -> bool {
+        self.state.is_running()
+    }
+
+    /// Starts a new game, from a `GameConfig` as JSON or, if none is given,
+    /// the current config with a new seed.
+    pub fn restart(&self, config: Option<String>) -> Result<(), JsValue> {
+        let config = match config {
+            Some(json) => {
+                serde_json::from_str(&json).map_err(|e| JsValue::from_str(&e.to_string()))?
+            }
+            None => self.state.next_config(),
+        };
+        self.state
+            .restart(&config)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// The current game's `GameConfig` as JSON, for passing back to `restart`.
+    pub fn config(&self) -> Result<String, JsValue> {
+        serde_json::to_string(&self.state.game.borrow().config)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// The game so far as a replay string, for `play_replay`.
+    pub fn replay(&self) -> Result<String, JsValue> {
+        serde_json::to_string(&self.state.input.replay())
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// Restarts and plays back a replay from `replay` instead of the keyboard.
+    pub fn play_replay(&self, replay: &str) -> Result<(), JsValue> {
+        let replay: Replay =
+            serde_json::from_str(replay).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        self.state
+            .play(&replay)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// Attract mode: the computer plays with `strategy` ("greedy",
+    /// "pathfinder" or "hamiltonian") until someone presses an arrow key.
+    pub fn demo(id: &str, width: u16, height: u16, strategy: &str) -> Result<Snake, JsValue> {
+        let strategy = strategy
+            .parse::<Strategy>()
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        let snake = Snake::create(id, GameConfig::new(width, height), false)?;
+        snake.state.set_autopilot(Some(strategy));
+        Ok(snake)
+    }
 
-        let canvas: web_sys::HtmlCanvasElement = canvas
+    /// Hands the game to the computer, or back to the keyboard with `None`.
+    /// Either way a new game starts.
+    pub fn set_autopilot(&self, strategy: Option<String>) -> Result<(), JsValue> {
+        let strategy = strategy
+            .map(|s| s.parse::<Strategy>())
+            .transpose()
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        self.state.set_autopilot(strategy);
+        Ok(())
+    }
+
+    /// Two players on one keyboard: arrow keys and WASD.
+    pub fn versus(id: &str, width: u16, height: u16, wrap: bool) -> Result<Snake, JsValue> {
+        Snake::create(id, GameConfig::versus(width, height, 2), wrap)
+    }
+
+    /// Plays on a level map in the `snake::level` text format.
+    pub fn with_level(id: &str, level: &str, wrap: bool) -> Result<Snake, JsValue> {
+        let level = Level::parse(level).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        Snake::create(id, GameConfig::from_level(&level), wrap)
+    }
+
+    fn create(id: &str, config: GameConfig, wrap: bool) -> Result<Snake, JsValue> {
+        set_panic_hook();
+
+        let walls = if wrap { Walls::Wrap } else { Walls::Solid };
+        let game = Game::new(&config.walls(walls).seed(rand::random()))
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        let replay = Replay::new(&game.config);
+        Ok(Snake::attach(id, game, replay))
+    }
+
+    /// Whether `load` has a game to pick up.
+    pub fn has_saved_game() -> bool {
+        LocalStore::storage()
+            .and_then(|storage| storage.get_item(SAVED_GAME))
+            .is_ok_and(|saved| saved.is_some())
+    }
+
+    /// Forgets the game in `localStorage`, such as one `load` can't read.
+    pub fn clear_saved_game() -> Result<(), JsValue> {
+        let storage = LocalStore::storage()?;
+        storage.remove_item(SAVED_GAME)?;
+        storage.remove_item(SAVED_REPLAY)
+    }
+
+    /// Picks up, paused, the game an earlier page left in `localStorage`.
+    pub fn load(id: &str) -> Result<Snake, JsValue> {
+        set_panic_hook();
+
+        let storage = LocalStore::storage()?;
+        let game: Game = match storage.get_item(SAVED_GAME)? {
+            Some(text) => {
+                serde_json::from_str(&text).map_err(|e| JsValue::from_str(&e.to_string()))?
+            }
+            None => return Err(JsValue::from_str("no saved game")),
+        };
+        // Without its recording the game still plays, it just can't be replayed.
+        let replay = storage
+            .get_item(SAVED_REPLAY)?
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_else(|| Replay::new(&game.config));
+
+        let snake = Snake::attach(id, game, replay);
+        snake.state.pause();
+        Ok(snake)
+    }
+
+    fn attach(id: &str, game: Game, replay: Replay) -> Snake {
+        let document = web_sys::window().unwrap().document().unwrap();
+        let canvas: web_sys::HtmlCanvasElement = document
+            .get_element_by_id(id)
+            .unwrap()
             .dyn_into::<web_sys::HtmlCanvasElement>()
             .map_err(|_| ())
             .unwrap();
@@ -181,44 +1340,141 @@ impl Snake {
             .dyn_into::<web_sys::CanvasRenderingContext2d>()
             .unwrap();
 
-        let display = CanvasDisplay::new(context, canvas.width(), canvas.height());
+        let state = Rc::new(State {
+            input: Recorder::resume(WebInput::new(game.snakes.len()), replay),
+            playback: RefCell::new(None),
+            autopilot: RefCell::new(None),
+            clock: RefCell::new(Clock::new(game.tick_interval())),
+            display: CanvasDisplay::new(canvas, context),
+            game: RefCell::new(game),
+            player: RefCell::new("player".to_string()),
+            unrecorded: RefCell::new(None),
+            paused: Cell::new(false),
+            frame: Cell::new(None),
+            animate: RefCell::new(None),
+            loading_sprites: RefCell::new(None),
+            on_sprites_load: RefCell::new(None),
+        });
 
-        display.initialize(&*self.game.borrow_mut());
+        // The loop only holds a weak reference so dropping the Snake frees
+        // the game.
+        let weak = Rc::downgrade(&state);
+        *state.animate.borrow_mut() = Some(Closure::wrap(Box::new(move |timestamp: f64| {
+            if let Some(state) = weak.upgrade() {
+                state.tick(timestamp);
+            }
+        })));
+        let weak = Rc::downgrade(&state);
+        *state.on_sprites_load.borrow_mut() = Some(Closure::wrap(Box::new(move || {
+            if let Some(state) = weak.upgrade() {
+                state.sprites_loaded();
+            }
+        })));
 
-        #[allow(clippy::type_complexity)]
-        let f: Rc<RefCell<Option<Closure<dyn FnMut()>>>> = Rc::new(RefCell::new(None));
-        let outer_f = f.clone();
-        {
-            let game_ref = self.game.clone();
-            let counter_ref = self.counter.clone();
-            let input_ref = self.input.clone();
-            let mut frames: u32 = 0;
-
-            *outer_f.borrow_mut() = Some(Closure::wrap(Box::new(move || {
-                let mut counter = counter_ref.borrow_mut();
-                let mut game = game_ref.borrow_mut();
-
-                if frames >= 10 {
-                    frames = 0;
-                    game_step(&mut counter, &mut game, &display, &*input_ref);
-                } else {
-                    frames += 1;
-                }
+        let keydown_state = state.clone();
+        let on_keydown: Closure<dyn FnMut(web_sys::KeyboardEvent)> =
+            Closure::wrap(Box::new(move |event: web_sys::KeyboardEvent| {
+                keydown_state.keydown(&event)
+            }));
+        document
+            .add_event_listener_with_callback("keydown", on_keydown.as_ref().unchecked_ref())
+            .expect("failed setting keydown handler");
 
-                if !game.game_over {
-                    web_sys::window()
-                        .unwrap()
-                        .request_animation_frame(
-                            f.borrow().as_ref().unwrap().as_ref().unchecked_ref(),
-                        )
-                        .expect("failed requesting animation frame");
-                }
-            })));
+        let resize_state = state.clone();
+        let on_resize: Closure<dyn FnMut()> =
+            Closure::wrap(Box::new(move || resize_state.resize()));
+        web_sys::window()
+            .unwrap()
+            .add_event_listener_with_callback("resize", on_resize.as_ref().unchecked_ref())
+            .expect("failed setting resize handler");
+
+        // Leaving or hiding the page is the last chance to save what has
+        // happened since the last save.
+        let hide_state = state.clone();
+        let on_hide: Closure<dyn FnMut()> = Closure::wrap(Box::new(move || hide_state.save()));
+        document
+            .add_event_listener_with_callback("visibilitychange", on_hide.as_ref().unchecked_ref())
+            .expect("failed setting visibilitychange handler");
+        web_sys::window()
+            .unwrap()
+            .add_event_listener_with_callback("pagehide", on_hide.as_ref().unchecked_ref())
+            .expect("failed setting pagehide handler");
+
+        state.display.fit();
+        state.display.initialize(&state.game.borrow());
+        state.schedule();
+
+        Snake {
+            state,
+            on_keydown,
+            on_resize,
+            on_hide,
         }
+    }
+}
 
+impl Drop for Snake {
+    fn drop(&mut self) {
+        self.state.cancel();
+        self.state.stop_loading_sprites();
         web_sys::window()
             .unwrap()
-            .request_animation_frame(outer_f.borrow().as_ref().unwrap().as_ref().unchecked_ref())
-            .expect("failed requesting animation frame");
+            .document()
+            .unwrap()
+            .remove_event_listener_with_callback(
+                "keydown",
+                self.on_keydown.as_ref().unchecked_ref(),
+            )
+            .expect("failed removing keydown handler");
+        web_sys::window()
+            .unwrap()
+            .document()
+            .unwrap()
+            .remove_event_listener_with_callback(
+                "visibilitychange",
+                self.on_hide.as_ref().unchecked_ref(),
+            )
+            .expect("failed removing visibilitychange handler");
+        web_sys::window()
+            .unwrap()
+            .remove_event_listener_with_callback("resize", self.on_resize.as_ref().unchecked_ref())
+            .expect("failed removing resize handler");
+        web_sys::window()
+            .unwrap()
+            .remove_event_listener_with_callback("pagehide", self.on_hide.as_ref().unchecked_ref())
+            .expect("failed removing pagehide handler");
+    }
+}
+
+/// Whether a key was pressed in a field on the page that takes typing, such
+/// as the high-score name, rather than for the game.
+fn typing(event: &web_sys::KeyboardEvent) -> bool {
+    let element = match event
+        .target()
+        .and_then(|target| target.dyn_into::<web_sys::HtmlElement>().ok())
+    {
+        Some(element) => element,
+        None => return false,
+    };
+    matches!(element.tag_name().as_str(), "INPUT" | "TEXTAREA" | "SELECT")
+        || element.is_content_editable()
+}
+
+/// Records the game in `localStorage` and lists the table over the board.
+/// Returns the table from before, if the game made it.
+fn record_high_score(game: &Game, name: &str, display: &CanvasDisplay) -> Option<HighScores> {
+    let result = HIGH_SCORES.load().and_then(|before| {
+        let (scores, rank) = highscores::record(&HIGH_SCORES, game, name)?;
+        Ok((before, scores, rank))
+    });
+    match result {
+        Ok((before, scores, rank)) => {
+            display.draw_high_scores(game, &scores, rank);
+            rank.map(|_| before)
+        }
+        Err(e) => {
+            web_sys::console::error_1(&e);
+            None
+        }
     }
 }
@@ -5,5 +5,31 @@ if (document.getElementById('smiley-canvas') !== null) {
 }
 
 if (document.getElementById('snake-canvas') !== null) {
-    Snake.new('snake-canvas', 15, 15);
+    let snake;
+    if (Snake.has_saved_game()) {
+        try {
+            snake = Snake.load('snake-canvas');
+        } catch (e) {
+            // A save this version can't read would break every reload.
+            console.error('Discarding saved game:', e);
+            Snake.clear_saved_game();
+        }
+    }
+    if (snake === undefined) {
+        snake = Snake.new('snake-canvas', 15, 15, false);
+    }
+
+    const rewind = document.getElementById('rewind');
+    if (rewind !== null) {
+        rewind.addEventListener('click', () => snake.rewind());
+    }
+
+    const theme = document.getElementById('theme');
+    if (theme !== null) {
+        theme.addEventListener('change', () => {
+            snake.set_theme(theme.value);
+            // Hand the keys back to the game rather than the select.
+            theme.blur();
+        });
+    }
 }